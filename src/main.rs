@@ -28,6 +28,9 @@
  * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 
+// lazy_static! expands recursively, once per metric.
+#![recursion_limit = "256"]
+
 #[forbid(unsafe_code)]
 #[macro_use]
 extern crate lazy_static;
@@ -77,11 +80,47 @@ fn main() {
                 )
                 .arg(
                     Arg::with_name("nfsv4opsclients")
-                        .help("Enable NFSv4 metrics operations per client (WARNING: can be CPU intensive)")
+                        .help("Enable NFSv4 metrics operations per client and states per export (WARNING: can be CPU intensive)")
                         .short("o")
                         .long("nfsv4-ops-clients")
                         .required(false)
                         .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("kerberos-only-exports")
+                        .help("Comma separated list of exported paths that must only allow Kerberos (sec=krb5*)")
+                        .short("k")
+                        .long("kerberos-only-exports")
+                        .required(false)
+                        .value_name("PATHS")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("export-probe-timeout")
+                        .help("Seconds given to stat an exported path before reporting it hung (default: 5)")
+                        .short("t")
+                        .long("export-probe-timeout")
+                        .required(false)
+                        .value_name("SECONDS")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("client-label")
+                        .help("Comma separated parts of the client label: address, ip, clientid, name (default: address)")
+                        .short("l")
+                        .long("client-label")
+                        .required(false)
+                        .value_name("SCHEME")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("hot-files")
+                        .help("Number of most opened and most locked files reported (default: 10)")
+                        .short("f")
+                        .long("hot-files")
+                        .required(false)
+                        .value_name("N")
+                        .takes_value(true),
                 ),
         )
         .get_matches();
@@ -1,2 +1,3 @@
 pub mod nfsv4;
 pub mod nfs_generic;
+pub mod exports;
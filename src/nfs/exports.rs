@@ -0,0 +1,678 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause
+ *
+ * BSD 2-Clause License
+ *
+ * Copyright (c) 2021-2023, Gandi S.A.S.
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice, this
+ *    list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use crate::utils::helper::{
+    path_exists, unescape_octal, wrapper_read, ETC_EXPORTS, ETC_EXPORTS_D, PROC_NFSDV4, VAR_NFSDV4,
+};
+use crate::utils::diskstats::{diskstats, DiskStats};
+use crate::utils::mountinfo::{mount_for_path, MountInfo};
+use nix::sys::statvfs::statvfs;
+use std::collections::HashSet;
+use std::fs::{metadata, read_dir};
+use std::sync::mpsc::channel;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+lazy_static! {
+    // Paths whose probe thread is still stuck in stat(), not probed again
+    // until it returns so a hung export does not pile up threads.
+    static ref PROBES_RUNNING: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    // rw, ro otherwise.
+    pub read_write: bool,
+    // sync, async otherwise.
+    pub sync: bool,
+    // root_squash, no_root_squash otherwise.
+    pub root_squash: bool,
+    // all_squash, no_all_squash otherwise.
+    pub all_squash: bool,
+    // secure (requests from ports below 1024), insecure otherwise.
+    pub secure: bool,
+    // subtree_check, no_subtree_check otherwise.
+    pub subtree_check: bool,
+    // security flavors from sec=, "sys", "krb5", "krb5i", "krb5p".
+    pub sec: Vec<String>,
+    // fsid= value, "0", "root", a number or an UUID.
+    pub fsid: Option<String>,
+    // every other option, as written.
+    pub other: Vec<String>,
+}
+
+// Defaults applied by exportfs to the options not given in /etc/exports.
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            read_write: false,
+            sync: true,
+            root_squash: true,
+            all_squash: false,
+            secure: true,
+            subtree_check: false,
+            sec: vec!["sys".to_string()],
+            fsid: None,
+            other: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    // exported path.
+    pub path: String,
+    // client specification, "*", "10.0.0.0/8", "@netgroup", a host name...
+    pub client: String,
+    pub options: ExportOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportStats {
+    // exported path.
+    pub path: String,
+    // client specification the export applies to, "*", "10.0.0.0/8"...
+    pub client: String,
+    // number of stale file handles returned for this export.
+    pub fh_stale: i64,
+    // total amount of bytes read from this export.
+    pub io_read: i64,
+    // total amount of bytes written to this export.
+    pub io_write: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportCapacity {
+    // exported path.
+    pub path: String,
+    // type of the filesystem holding the path, from mountinfo.
+    pub fstype: String,
+    // filesystem size, free space and space available to non-root users,
+    // in bytes.
+    pub size: i64,
+    pub free: i64,
+    pub available: i64,
+    // total and free inodes.
+    pub files: i64,
+    pub files_free: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportProbe {
+    // exported path.
+    pub path: String,
+    // stat() returned before the timeout, successfully.
+    pub success: bool,
+    // time stat() took, the timeout when it did not return.
+    pub duration: Duration,
+}
+
+// Options are applied in order on top of `options`, a later option wins
+// over an earlier one like it does for exportfs.
+pub fn parse_export_options(list: &str, mut options: ExportOptions) -> ExportOptions {
+    // etab repeats sec= for every group of flavors sharing the same options,
+    // "sec=krb5p,rw,...,sec=sys,ro,...", the flavors add up.
+    let mut sec_seen = false;
+
+    for option in list.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        match option {
+            "rw" => options.read_write = true,
+            "ro" => options.read_write = false,
+            "sync" => options.sync = true,
+            "async" => options.sync = false,
+            "root_squash" => options.root_squash = true,
+            "no_root_squash" => options.root_squash = false,
+            "all_squash" => options.all_squash = true,
+            "no_all_squash" => options.all_squash = false,
+            "secure" => options.secure = true,
+            "insecure" => options.secure = false,
+            "subtree_check" => options.subtree_check = true,
+            "no_subtree_check" => options.subtree_check = false,
+            _ => {
+                if let Some(sec) = option.strip_prefix("sec=") {
+                    if !sec_seen {
+                        options.sec.clear();
+                        sec_seen = true;
+                    }
+                    for flavor in sec.split(':') {
+                        if !options.sec.iter().any(|x| x == flavor) {
+                            options.sec.push(flavor.to_string());
+                        }
+                    }
+                } else if let Some(fsid) = option.strip_prefix("fsid=") {
+                    options.fsid = Some(fsid.to_string());
+                } else if !options.other.iter().any(|x| x == option) {
+                    options.other.push(option.to_string());
+                }
+            }
+        }
+    }
+
+    options
+}
+
+// A client looks like "10.0.0.0/8(rw,sync)", the options are optional.
+pub fn parse_export_client(path: &str, client: &str, defaults: &ExportOptions) -> Export {
+    let (client, options) = match client.split_once('(') {
+        Some((client, options)) => (client, parse_export_options(options.trim_end_matches(')'), defaults.clone())),
+        None => (client, defaults.clone()),
+    };
+
+    Export {
+        path: path.to_string(),
+        client: if client.is_empty() { "*".to_string() } else { unescape_octal(client) },
+        options,
+    }
+}
+
+// etab is written by exportfs with every option spelled out, one
+// "<path>\t<client>(<options>)" line per export and client.
+fn parse_etab(content: &[String]) -> Vec<Export> {
+    let mut exports: Vec<Export> = Vec::new();
+
+    for line in content.iter() {
+        let data: Vec<&str> = line.split_whitespace().collect();
+        if data.len() < 2 {
+            continue;
+        }
+        let path = unescape_octal(data[0]);
+        exports.push(parse_export_client(&path, data[1], &ExportOptions::default()));
+    }
+
+    exports
+}
+
+pub fn etab_exports() -> Vec<Export> {
+    let etab = VAR_NFSDV4.to_owned() + "etab";
+
+    if path_exists(&etab) {
+        let content = wrapper_read(etab);
+        return parse_etab(&content);
+    }
+
+    Vec::new()
+}
+
+// Split a line of /etc/exports in words, a path can be double-quoted to
+// hold spaces and anything after an unquoted # is a comment.
+fn exports_words(line: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => break,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(word.clone());
+                    word.clear();
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+// exports(5): "<path> [-<default options>] [<client>[(<options>)]]...",
+// lines ending with a backslash continue on the next one. A path without
+// client is exported to everybody.
+fn parse_exports_file(content: &[String]) -> Vec<Export> {
+    let mut exports: Vec<Export> = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+
+    for line in content.iter() {
+        match line.trim_end().strip_suffix('\\') {
+            Some(line) => {
+                current.push_str(line);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                lines.push(current.clone());
+                current.clear();
+            }
+        }
+    }
+    lines.push(current);
+
+    for line in lines.iter() {
+        let words = exports_words(line);
+        let path = match words.first() {
+            Some(path) => unescape_octal(path),
+            None => continue,
+        };
+
+        let mut defaults = ExportOptions::default();
+        let mut clients: Vec<&String> = Vec::new();
+        for word in words.iter().skip(1) {
+            match word.strip_prefix('-') {
+                Some(options) => defaults = parse_export_options(options, defaults),
+                None => clients.push(word),
+            }
+        }
+
+        if clients.is_empty() {
+            exports.push(parse_export_client(&path, "*", &defaults));
+        }
+        for client in clients {
+            exports.push(parse_export_client(&path, client, &defaults));
+        }
+    }
+
+    exports
+}
+
+// Exports configured in /etc/exports and /etc/exports.d/*.exports, whether
+// exportfs was run since or not.
+pub fn configured_exports() -> Vec<Export> {
+    let mut files: Vec<String> = Vec::new();
+    let mut exports: Vec<Export> = Vec::new();
+
+    if path_exists(ETC_EXPORTS) {
+        files.push(ETC_EXPORTS.to_string());
+    }
+    if path_exists(ETC_EXPORTS_D) {
+        match read_dir(ETC_EXPORTS_D) {
+            Ok(entries) => {
+                let mut paths: Vec<String> = entries
+                    .filter_map(|x| x.ok())
+                    .map(|x| x.path().to_string_lossy().to_string())
+                    .filter(|x| x.ends_with(".exports"))
+                    .collect();
+                paths.sort();
+                files.append(&mut paths);
+            },
+            Err(m) => println!("Could not open: {:?}", m),
+        }
+    }
+
+    for file in files {
+        let content = wrapper_read(file);
+        exports.append(&mut parse_exports_file(&content));
+    }
+
+    exports
+}
+
+// etab lists every option while /etc/exports only has the ones the admin
+// wrote, both are compared after applying the exportfs defaults. Options
+// kept in `other` are left out, exportfs adds some of them on its own.
+fn same_export_options(configured: &ExportOptions, active: &ExportOptions) -> bool {
+    configured.read_write == active.read_write
+        && configured.sync == active.sync
+        && configured.root_squash == active.root_squash
+        && configured.all_squash == active.all_squash
+        && configured.secure == active.secure
+        && configured.subtree_check == active.subtree_check
+        && configured.sec == active.sec
+        && configured.fsid == active.fsid
+}
+
+// Returns (path, client, state) for every export that differs between the
+// configuration and etab: "added" when configured but not exported yet,
+// "removed" when still exported but no longer configured, "changed" when
+// the options differ.
+pub fn exports_drift(configured: &[Export], active: &[Export]) -> Vec<(String, String, String)> {
+    let mut drift: Vec<(String, String, String)> = Vec::new();
+
+    for export in configured.iter() {
+        match active.iter().find(|x| x.path == export.path && x.client == export.client) {
+            Some(x) if same_export_options(&export.options, &x.options) => (),
+            Some(_) => drift.push((export.path.clone(), export.client.clone(), "changed".to_string())),
+            None => drift.push((export.path.clone(), export.client.clone(), "added".to_string())),
+        }
+    }
+    for export in active.iter() {
+        if !configured.iter().any(|x| x.path == export.path && x.client == export.client) {
+            drift.push((export.path.clone(), export.client.clone(), "removed".to_string()));
+        }
+    }
+
+    drift
+}
+
+// Risky settings checked by export_risks(), one gauge per path and rule.
+pub const EXPORT_RISK_RULES: [&str; 5] = [
+    "no_root_squash",
+    "insecure",
+    "world_rw",
+    "sec_sys",
+    "subtree_check_mismatch",
+];
+
+// Exported paths, once each whatever the number of client specifications.
+pub fn exported_paths(exports: &[Export]) -> Vec<&str> {
+    let mut paths: Vec<&str> = exports.iter().map(|x| x.path.as_str()).collect();
+    paths.sort_unstable();
+    paths.dedup();
+    paths
+}
+
+// Returns (path, rule, at risk) for every exported path and rule. A path is
+// at risk when one of its client specifications is, except for
+// subtree_check_mismatch which flags paths whose client specifications
+// disagree on subtree checking. sec_sys only applies to the paths listed in
+// `kerberos_only`.
+pub fn export_risks(exports: &[Export], kerberos_only: &[String]) -> Vec<(String, String, bool)> {
+    let mut risks: Vec<(String, String, bool)> = Vec::new();
+
+    for path in exported_paths(exports) {
+        let clients: Vec<&ExportOptions> = exports.iter().filter(|x| x.path == path).map(|x| &x.options).collect();
+        let world_rw = exports.iter().any(|x| x.path == path && x.client == "*" && x.options.read_write);
+
+        for rule in EXPORT_RISK_RULES.iter() {
+            let at_risk = match *rule {
+                "no_root_squash" => clients.iter().any(|x| !x.root_squash),
+                "insecure" => clients.iter().any(|x| !x.secure),
+                "world_rw" => world_rw,
+                "sec_sys" => kerberos_only.iter().any(|x| x == path) && clients.iter().any(|x| x.sec.iter().any(|x| x == "sys")),
+                "subtree_check_mismatch" => clients.iter().any(|x| x.subtree_check != clients[0].subtree_check),
+                _ => false,
+            };
+            risks.push((path.to_string(), rule.to_string(), at_risk));
+        }
+    }
+
+    risks
+}
+
+// statvfs() of every exported path, once per path whatever the number of
+// client specifications. Paths that cannot be stat'ed are left out.
+pub fn exports_capacity(exports: &[Export], mounts: &[MountInfo]) -> Vec<ExportCapacity> {
+    let mut capacities: Vec<ExportCapacity> = Vec::new();
+
+    for path in exported_paths(exports) {
+        let stat = match statvfs(path) {
+            Ok(stat) => stat,
+            Err(_) => continue,
+        };
+        let fragment_size = stat.fragment_size() as i64;
+
+        capacities.push(ExportCapacity {
+            path: path.to_string(),
+            fstype: mount_for_path(mounts, path).map(|x| x.fstype.clone()).unwrap_or_default(),
+            size: stat.blocks() as i64 * fragment_size,
+            free: stat.blocks_free() as i64 * fragment_size,
+            available: stat.blocks_available() as i64 * fragment_size,
+            files: stat.files() as i64,
+            files_free: stat.files_free() as i64,
+        });
+    }
+
+    capacities
+}
+
+// stat() every exported path from its own thread, so a path whose getattr
+// hangs blocks that thread and not the scrape. Probes run in parallel and
+// share the same deadline. The inode of an export root is pinned by the nfsd
+// export cache: on a local filesystem (ext4, XFS) the stat is answered from
+// memory and a dead block device goes unnoticed. Only filesystems doing the
+// getattr remotely (NFS re-exports, FUSE...) or stuck on a lock hang it.
+pub fn probe_exports(exports: &[Export], timeout: Duration) -> Vec<ExportProbe> {
+    let mut probes: Vec<ExportProbe> = Vec::new();
+    let mut waiting = Vec::new();
+
+    for path in exported_paths(exports) {
+        if !PROBES_RUNNING.lock().unwrap().insert(path.to_string()) {
+            probes.push(ExportProbe { path: path.to_string(), success: false, duration: timeout });
+            continue;
+        }
+
+        let (sender, receiver) = channel();
+        let probe_path = path.to_string();
+        thread::spawn(move || {
+            let start = Instant::now();
+            let success = metadata(&probe_path).is_ok();
+            PROBES_RUNNING.lock().unwrap().remove(&probe_path);
+            // the scrape may have given up on us already.
+            let _ = sender.send((success, start.elapsed()));
+        });
+        waiting.push((path.to_string(), receiver));
+    }
+
+    let deadline = Instant::now() + timeout;
+    for (path, receiver) in waiting {
+        let probe = match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok((success, duration)) => ExportProbe { path, success, duration },
+            Err(_) => ExportProbe { path, success: false, duration: timeout },
+        };
+        probes.push(probe);
+    }
+
+    probes
+}
+
+// (path, I/O statistics of the block device holding it) for every exported
+// path. Filesystems without a block device of their own (tmpfs, NFS, btrfs
+// subvolumes on an anonymous device...) are left out.
+pub fn exports_diskstats(exports: &[Export], mounts: &[MountInfo]) -> Vec<(String, DiskStats)> {
+    let mut stats: Vec<(String, DiskStats)> = Vec::new();
+    let disks = diskstats();
+
+    for path in exported_paths(exports) {
+        let mount = match mount_for_path(mounts, path) {
+            Some(mount) => mount,
+            None => continue,
+        };
+        if let Some(disk) = disks.iter().find(|x| x.major == mount.major && x.minor == mount.minor) {
+            stats.push((path.to_string(), disk.clone()));
+        }
+    }
+
+    stats
+}
+
+// Filesystem holding one or more exported paths. NFSv4 states only carry
+// the device, exports sharing a filesystem cannot be told apart from them.
+#[derive(Debug, Clone, Default)]
+pub struct ExportDevice {
+    pub major: u64,
+    pub minor: u64,
+    // mount point of the filesystem.
+    pub mount_point: String,
+    // exported paths on the filesystem, sorted.
+    pub exports: Vec<String>,
+}
+
+// Filesystems holding the exported paths. A filesystem bind mounted in
+// several places is reported at the mount of its first exported path.
+pub fn exports_devices(exports: &[Export], mounts: &[MountInfo]) -> Vec<ExportDevice> {
+    let mut devices: Vec<ExportDevice> = Vec::new();
+
+    for path in exported_paths(exports) {
+        let mount = match mount_for_path(mounts, path) {
+            Some(mount) => mount,
+            None => continue,
+        };
+        match devices.iter_mut().find(|x| x.major == mount.major && x.minor == mount.minor) {
+            Some(device) => device.exports.push(path.to_string()),
+            None => devices.push(ExportDevice {
+                major: mount.major,
+                minor: mount.minor,
+                mount_point: mount.mount_point.clone(),
+                exports: vec![path.to_string()],
+            }),
+        }
+    }
+
+    devices
+}
+
+// Value of the `export` label of states on `device`: the exported path, or
+// the mount point when several exports share the filesystem.
+pub fn device_export(device: &ExportDevice) -> &str {
+    match device.exports.as_slice() {
+        [export] => export,
+        _ => &device.mount_point,
+    }
+}
+
+// export_stats walks the nfsd.export cache like the exports file does, but
+// shows the per-export counters (Linux 6.2) instead of the options:
+//
+// # Path Client Start-time
+// #	Stats
+// /export	*	92
+// 	fh_stale: 0
+// 	io_read: 9
+// 	io_write: 0
+pub fn exports_stats() -> Vec<ExportStats> {
+    let mut exports: Vec<ExportStats> = Vec::new();
+    let export_stats = PROC_NFSDV4.to_owned() + "export_stats";
+
+    if path_exists(&export_stats) {
+        let content = wrapper_read(export_stats);
+
+        for line in content.iter().filter(|x| !x.starts_with('#')) {
+            if line.starts_with(char::is_whitespace) {
+                let export = match exports.last_mut() {
+                    Some(export) => export,
+                    None => continue,
+                };
+                if let Some((key, value)) = line.trim().split_once(':') {
+                    let value = value.trim().parse::<i64>().unwrap_or(0);
+                    match key {
+                        "fh_stale" => export.fh_stale = value,
+                        "io_read" => export.io_read = value,
+                        "io_write" => export.io_write = value,
+                        _ => (),
+                    }
+                }
+                continue;
+            }
+
+            let data: Vec<&str> = line.split_whitespace().collect();
+            exports.push(ExportStats {
+                path: unescape_octal(data[0]),
+                client: data.get(1).map(|x| unescape_octal(x)).unwrap_or_default(),
+                ..ExportStats::default()
+            });
+        }
+    }
+
+    exports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(content: &str) -> Vec<String> {
+        content.lines().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn exports_words_keep_quoted_spaces_and_drop_comments() {
+        assert_eq!(
+            exports_words(r#""/srv/my share" host(rw) # "not a path" host2"#),
+            vec!["/srv/my share", "host(rw)"],
+        );
+        assert_eq!(exports_words("/srv/a\t*(ro)"), vec!["/srv/a", "*(ro)"]);
+        assert!(exports_words("# only a comment").is_empty());
+    }
+
+    #[test]
+    fn exports_file_handles_continuations_defaults_and_bare_paths() {
+        let exports = parse_exports_file(&lines("\
+# /etc/exports
+/srv/home -rw,no_subtree_check \\
+    10.0.0.0/8 192.168.0.1(ro,no_root_squash)
+/srv/public
+\"/srv/my share\" @admins(rw,sec=krb5p)
+"));
+
+        let found: Vec<(&str, &str)> = exports.iter().map(|x| (x.path.as_str(), x.client.as_str())).collect();
+        assert_eq!(found, vec![
+            ("/srv/home", "10.0.0.0/8"),
+            ("/srv/home", "192.168.0.1"),
+            ("/srv/public", "*"),
+            ("/srv/my share", "@admins"),
+        ]);
+        // -defaults apply to every client, their own options come on top.
+        assert!(exports[0].options.read_write);
+        assert!(!exports[1].options.read_write);
+        assert!(!exports[1].options.root_squash);
+        assert!(!exports[1].options.subtree_check);
+        // a path without client is exported read-only with the defaults.
+        assert_eq!(exports[2].options, ExportOptions::default());
+        assert_eq!(exports[3].options.sec, vec!["krb5p"]);
+    }
+
+    #[test]
+    fn etab_line_with_per_flavor_options() {
+        let exports = parse_etab(&lines("\
+/srv/my\\040share\t192.168.0.0/24(rw,sync,wdelay,hide,nocrossmnt,secure,root_squash,no_all_squash,no_subtree_check,secure_locks,acl,no_pnfs,fsid=7,anonuid=65534,anongid=65534,sec=krb5p,rw,secure,root_squash,no_all_squash,sec=sys,rw,secure,root_squash,no_all_squash)
+/srv/public\t*(ro,sync,wdelay,hide,nocrossmnt,insecure,root_squash,no_all_squash,no_subtree_check,secure_locks,acl,no_pnfs,anonuid=65534,anongid=65534,sec=sys,ro,insecure,root_squash,no_all_squash)
+"));
+
+        assert_eq!(exports.len(), 2);
+        assert_eq!(exports[0].path, "/srv/my share");
+        assert_eq!(exports[0].client, "192.168.0.0/24");
+        let options = &exports[0].options;
+        assert!(options.read_write && options.sync && options.root_squash && options.secure);
+        assert!(!options.all_squash && !options.subtree_check);
+        assert_eq!(options.sec, vec!["krb5p", "sys"]);
+        assert_eq!(options.fsid.as_deref(), Some("7"));
+        assert!(options.other.iter().any(|x| x == "anonuid=65534"));
+        assert!(!exports[1].options.read_write && !exports[1].options.secure);
+    }
+
+    #[test]
+    fn drift_compares_with_exportfs_defaults() {
+        let configured = parse_exports_file(&lines("\
+/srv/same 10.0.0.0/8(rw)
+/srv/changed 10.0.0.0/8(rw,no_root_squash)
+/srv/added *(ro)
+"));
+        let active = parse_etab(&lines("\
+/srv/same\t10.0.0.0/8(rw,sync,wdelay,hide,secure,root_squash,no_all_squash,no_subtree_check,sec=sys,rw,secure,root_squash,no_all_squash)
+/srv/changed\t10.0.0.0/8(rw,sync,wdelay,hide,secure,root_squash,no_all_squash,no_subtree_check,sec=sys,rw,secure,root_squash,no_all_squash)
+/srv/removed\t*(ro,sync,wdelay,hide,secure,root_squash,no_all_squash,no_subtree_check,sec=sys,ro,secure,root_squash,no_all_squash)
+"));
+
+        let mut drift = exports_drift(&configured, &active);
+        drift.sort();
+        assert_eq!(drift, vec![
+            ("/srv/added".to_string(), "*".to_string(), "added".to_string()),
+            ("/srv/changed".to_string(), "10.0.0.0/8".to_string(), "changed".to_string()),
+            ("/srv/removed".to_string(), "*".to_string(), "removed".to_string()),
+        ]);
+        assert!(exports_drift(&configured[..1], &active[..1]).is_empty());
+    }
+}
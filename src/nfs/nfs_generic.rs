@@ -28,9 +28,10 @@
  * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 
-use crate::utils::helper::{path_exists, wrapper_read, PROC_RPC};
+use crate::utils::helper::{path_exists, split_address_port, wrapper_read, PROC_NFSDV4, PROC_RPC, SYS_MODULE_NFSD};
+use std::fs::read_dir;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct ReplyCache {
     // client did not receive a reply, do a re-transmit request and
     // the reply was cached.
@@ -41,7 +42,7 @@ pub struct ReplyCache {
     pub nocache: i64,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct IOBytes {
     // total amount of bytes read since the last restart.
     pub read: i64,
@@ -50,7 +51,7 @@ pub struct IOBytes {
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub struct NetworkUsage {
     // total amount of packets.
     pub netcount: i64,
@@ -62,98 +63,793 @@ pub struct NetworkUsage {
     pub TCPconnect: i64,
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReplyCacheStats {
+    // maximum number of entries the cache can hold.
+    pub max_entries: i64,
+    // number of entries currently cached.
+    pub num_entries: i64,
+    // number of hash buckets.
+    pub hash_buckets: i64,
+    // memory used by the cached replies, in bytes.
+    pub mem_usage: i64,
+    // replies not cached because they did not fit in the entry.
+    pub payload_misses: i64,
+    // length of the longest hash chain seen.
+    pub longest_chain_len: i64,
+    // number of entries in the cache when that chain was seen.
+    pub cachesize_at_longest: i64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileHandle {
+    // number of stale file handles returned to clients.
+    pub stale: i64,
+    // the remaining fields are no longer updated by recent kernels.
+    pub total_lookups: i64,
+    pub anon_lookups: i64,
+    pub dir_not_in_cache: i64,
+    pub nondir_not_in_cache: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Threads {
+    // number of nfsd threads.
+    pub threads: i64,
+    // number of times all the threads were busy (always 0 since 4.3).
+    pub fullcnt: i64,
+    // thread usage histogram, no longer updated by recent kernels.
+    pub histogram: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReadAhead {
+    // size of the read-ahead cache.
+    pub cache_size: i64,
+    // number of times an entry was found at each 10% of the cache depth.
+    pub depths: Vec<i64>,
+    // number of times an entry was not found.
+    pub not_found: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RpcCalls {
+    // total amount of RPC calls.
+    pub calls: i64,
+    // total amount of rejected calls, sum of the next three.
+    pub badcalls: i64,
+    // calls with a malformed header.
+    pub badfmt: i64,
+    // calls with invalid credentials.
+    pub badauth: i64,
+    // calls from clients the server does not know.
+    pub badclnt: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Nfsv4OpStats {
+    // (operation name, number of calls) ordered by operation number, as
+    // reported by the proc4ops line.
+    pub operations: Vec<(String, i64)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NfsProcStats {
+    // NFS protocol version, "2", "3" or "4".
+    pub version: String,
+    // (procedure name, number of calls) ordered by procedure number, as
+    // reported by the proc2, proc3 and proc4 lines.
+    pub procedures: Vec<(String, i64)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PoolStats {
+    // pool number, there is one pool per NUMA node or CPU depending on the
+    // pool_mode module parameter.
+    pub pool: String,
+    // total amount of packets received by the pool.
+    pub packets_arrived: i64,
+    // times a socket was queued because no thread was free to handle it.
+    pub sockets_enqueued: i64,
+    // times an idle thread was woken up to handle a socket.
+    pub threads_woken: i64,
+    // times a thread gave up waiting for work.
+    pub threads_timedout: i64,
+    // number of threads of the pool, from pool_threads.
+    pub threads: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileCache {
+    // 1 for the layout of Linux 5.4 to 5.19 (total entries, longest chain,
+    // cache hits), 2 for the layout introduced in Linux 6.0.
+    pub format_version: i64,
+    // number of files in the cache (total inodes since Linux 6.2).
+    pub total_entries: i64,
+    // number of hash buckets.
+    pub hash_buckets: i64,
+    // number of files on the LRU list, ready to be evicted.
+    pub lru_entries: i64,
+    // lookups that found the file already open.
+    pub cache_hits: i64,
+    // total amount of file lookups.
+    pub acquisitions: i64,
+    // files opened and added to the cache.
+    pub allocations: i64,
+    // files released from the cache.
+    pub releases: i64,
+    // files evicted by the LRU.
+    pub evictions: i64,
+    // mean age of the released files in milliseconds.
+    pub mean_age_ms: i64,
+    // length of the longest hash chain, version 1 only.
+    pub longest_chain: i64,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct InflightRpc {
+    pub xid: String,
+    pub flags: String,
+    // NFS version, "NFSv3", "NFSv4"...
+    pub version: String,
+    // procedure name as printed by the kernel, "COMPOUND" for NFSv4.
+    pub procedure: String,
+    // time the request was received, in microseconds since boot.
+    pub timestamp_us: i64,
+    pub client_address: String,
+    pub client_port: String,
+    pub server_address: String,
+    pub server_port: String,
+    // first operations of the compound named like the proc4ops ones,
+    // "sequence", "putfh"... NFSv4 only.
+    pub compound_ops: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerConfig {
+    // enabled NFS versions, "3", "4", "4.1"...
+    pub versions: Vec<String>,
+    // number of threads requested, from threads.
+    pub threads: i64,
+    // NFSv4 lease and grace periods in seconds.
+    pub lease_time: i64,
+    pub grace_time: i64,
+    // largest read or write size, in bytes.
+    pub max_block_size: i64,
+    // maximum number of connections, 0 means no limit.
+    pub max_connections: i64,
+    // listening sockets, "tcp/2049", "rdma/20049"...
+    pub portlist: Vec<String>,
+    // (name, value) of the nfsd module parameters.
+    pub module_parameters: Vec<(String, String)>,
+}
+
+// Everything /proc/net/rpc/nfsd has to say, one field per line. Lines this
+// parser does not know are kept as they are in `unknown`.
+#[derive(Debug, Clone, Default)]
+pub struct NfsdRpcStats {
+    pub reply_cache: ReplyCache,
+    pub file_handle: FileHandle,
+    pub io_bytes: IOBytes,
+    pub threads: Threads,
+    // the ra line was removed in Linux 5.11.
+    pub read_ahead: Option<ReadAhead>,
+    pub network_usage: NetworkUsage,
+    pub rpc_calls: RpcCalls,
+    pub nfsv2_procs: NfsProcStats,
+    pub nfsv3_procs: NfsProcStats,
+    // procedures of the proc4 line, only null and compound.
+    pub nfsv4_procs: NfsProcStats,
+    pub nfsv4_ops: Nfsv4OpStats,
+    // GETATTR conflicting with a write delegation, the wdeleg_getattr line
+    // added in Linux 6.6.
+    pub wdeleg_getattr: Option<i64>,
+    // (first field, remaining fields) of every other line.
+    pub unknown: Vec<(String, Vec<String>)>,
+}
+
 #[derive(Debug)]
 pub struct NfsStats {
     pub reply_cache: ReplyCache,
+    // only published by kernels having /proc/fs/nfsd/reply_cache_stats.
+    pub reply_cache_stats: Option<ReplyCacheStats>,
     pub io_bytes: IOBytes,
     pub network_usage: NetworkUsage,
+    pub rpc_calls: RpcCalls,
+    pub threads: Threads,
+    pub nfsv4_ops: Nfsv4OpStats,
+    pub nfsv2_procs: NfsProcStats,
+    pub nfsv3_procs: NfsProcStats,
+}
+
+// NFSv2 procedure names indexed by procedure number (RFC 1094).
+const NFSV2_PROCEDURES: [&str; 18] = [
+    "null", "getattr", "setattr", "root", "lookup", "readlink", "read",
+    "writecache", "write", "create", "remove", "rename", "link", "symlink",
+    "mkdir", "rmdir", "readdir", "statfs",
+];
+
+// NFSv3 procedure names indexed by procedure number (RFC 1813).
+const NFSV3_PROCEDURES: [&str; 22] = [
+    "null", "getattr", "setattr", "lookup", "access", "readlink", "read",
+    "write", "create", "mkdir", "symlink", "mknod", "remove", "rmdir",
+    "rename", "link", "readdir", "readdirplus", "fsstat", "fsinfo",
+    "pathconf", "commit",
+];
+
+// NFSv4 operation names indexed by operation number (RFC 5661, RFC 7862 and
+// RFC 8276 for the xattr operations). Numbers 0 to 2 are not assigned to any
+// operation, the kernel reports them anyway.
+const NFSV4_OPERATIONS: [&str; 76] = [
+    "", "", "",
+    "access", "close", "commit", "create", "delegpurge", "delegreturn",
+    "getattr", "getfh", "link", "lock", "lockt", "locku", "lookup",
+    "lookupp", "nverify", "open", "openattr", "open_confirm",
+    "open_downgrade", "putfh", "putpubfh", "putrootfh", "read", "readdir",
+    "readlink", "remove", "rename", "renew", "restorefh", "savefh",
+    "secinfo", "setattr", "setclientid", "setclientid_confirm", "verify",
+    "write", "release_lockowner",
+    // NFSv4.1
+    "backchannel_ctl", "bind_conn_to_session", "exchange_id",
+    "create_session", "destroy_session", "free_stateid",
+    "get_dir_delegation", "getdeviceinfo", "getdevicelist", "layoutcommit",
+    "layoutget", "layoutreturn", "secinfo_no_name", "sequence", "set_ssv",
+    "test_stateid", "want_delegation", "destroy_clientid",
+    "reclaim_complete",
+    // NFSv4.2
+    "allocate", "copy", "copy_notify", "deallocate", "io_advise",
+    "layouterror", "layoutstats", "offload_cancel", "offload_status",
+    "read_plus", "seek", "write_same", "clone",
+    // Extended attributes
+    "getxattr", "setxattr", "listxattrs", "removexattr",
+];
+
+// NFSv4 procedure names indexed by procedure number (RFC 7530).
+const NFSV4_PROCEDURES: [&str; 2] = ["null", "compound"];
+
+#[inline]
+fn parse_i64(value: &str) -> i64 {
+    value.parse::<i64>().unwrap_or(0)
 }
 
 fn reply_cache(data: Vec<&str>) -> ReplyCache {
     let (mut hits, mut misses, mut nocache): (i64, i64, i64) = (0, 0, 0);
     if data.len() >= 4 {
-        hits = data[1].parse::<i64>().unwrap();
-        misses = data[2].parse::<i64>().unwrap();
-        nocache = data[3].parse::<i64>().unwrap();
+        hits = parse_i64(data[1]);
+        misses = parse_i64(data[2]);
+        nocache = parse_i64(data[3]);
     }
 
-    let reply_cache = ReplyCache {
-        hits: hits,
-        misses: misses,
-        nocache: nocache,
-    };
+    ReplyCache {
+        hits,
+        misses,
+        nocache,
+    }
+}
+
+fn file_handle(data: Vec<&str>) -> FileHandle {
+    let values: Vec<i64> = data.iter().skip(1).map(|x| parse_i64(x)).collect();
+    let value = |index: usize| values.get(index).copied().unwrap_or(0);
 
-    reply_cache
+    FileHandle {
+        stale: value(0),
+        total_lookups: value(1),
+        anon_lookups: value(2),
+        dir_not_in_cache: value(3),
+        nondir_not_in_cache: value(4),
+    }
 }
 
 fn io_bytes(data: Vec<&str>) -> IOBytes {
     let (mut read, mut write): (i64, i64) = (0, 0);
     if data.len() >= 3 {
-        read = data[1].parse::<i64>().unwrap();
-        write = data[2].parse::<i64>().unwrap();
+        read = parse_i64(data[1]);
+        write = parse_i64(data[2]);
     }
 
-    let io_bytes = IOBytes {
-        read: read,
-        write: write,
-    };
+    IOBytes {
+        read,
+        write,
+    }
+}
+
+// The line looks like "th <threads> <fullcnt> <10 histogram buckets>".
+fn threads(data: Vec<&str>) -> Threads {
+    let mut threads = Threads::default();
+    if data.len() >= 3 {
+        threads.threads = parse_i64(data[1]);
+        threads.fullcnt = parse_i64(data[2]);
+        threads.histogram = data.iter().skip(3).map(|x| x.parse::<f64>().unwrap_or(0.0)).collect();
+    }
+
+    threads
+}
+
+// The line looks like "ra <cache size> <10 depth buckets> <not found>".
+fn read_ahead(data: Vec<&str>) -> ReadAhead {
+    let mut read_ahead = ReadAhead::default();
+    if data.len() >= 3 {
+        let values: Vec<i64> = data.iter().skip(1).map(|x| parse_i64(x)).collect();
+        read_ahead.cache_size = values[0];
+        read_ahead.not_found = values[values.len() - 1];
+        read_ahead.depths = values[1..values.len() - 1].to_vec();
+    }
 
-    io_bytes
+    read_ahead
 }
 
 #[allow(non_snake_case)]
 fn network_usage(data: Vec<&str>) -> NetworkUsage {
     let (mut netcount, mut UDPcount, mut TCPcount, mut TCPconnect): (i64, i64, i64, i64) = (0, 0, 0, 0);
     if data.len() >= 5 {
-        netcount = data[1].parse::<i64>().unwrap();
-        UDPcount = data[2].parse::<i64>().unwrap();
-        TCPcount = data[3].parse::<i64>().unwrap();
-        TCPconnect = data[4].parse::<i64>().unwrap();
+        netcount = parse_i64(data[1]);
+        UDPcount = parse_i64(data[2]);
+        TCPcount = parse_i64(data[3]);
+        TCPconnect = parse_i64(data[4]);
+    }
+
+    NetworkUsage {
+        netcount,
+        UDPcount,
+        TCPcount,
+        TCPconnect,
+    }
+}
+
+fn rpc_calls(data: Vec<&str>) -> RpcCalls {
+    let values: Vec<i64> = data.iter().skip(1).map(|x| parse_i64(x)).collect();
+    let value = |index: usize| values.get(index).copied().unwrap_or(0);
+
+    RpcCalls {
+        calls: value(0),
+        badcalls: value(1),
+        badfmt: value(2),
+        badauth: value(3),
+        badclnt: value(4),
+    }
+}
+
+// The line looks like "proc4ops <count> <op0> <op1> ...". The count tells how
+// many operations the running kernel knows about, so trust it rather than our
+// table: operations we do not know yet are reported as "op<number>" and the
+// ones the kernel does not report are left out.
+fn nfsv4_ops(data: Vec<&str>) -> Nfsv4OpStats {
+    let mut operations: Vec<(String, i64)> = Vec::new();
+    if data.len() >= 2 {
+        let count = data[1].parse::<usize>().unwrap_or(0);
+        for (index, value) in data.iter().skip(2).take(count).enumerate() {
+            let name = match NFSV4_OPERATIONS.get(index) {
+                Some(&"") => continue,
+                Some(name) => name.to_string(),
+                None => format!("op{}", index),
+            };
+            operations.push((name, parse_i64(value)));
+        }
+    }
+
+    Nfsv4OpStats { operations }
+}
+
+// The lines look like "proc3 <count> <proc0> <proc1> ...", the same layout
+// as proc4ops, without unassigned numbers.
+fn nfs_procs(version: &str, names: &[&str], data: Vec<&str>) -> NfsProcStats {
+    let mut procedures: Vec<(String, i64)> = Vec::new();
+    if data.len() >= 2 {
+        let count = data[1].parse::<usize>().unwrap_or(0);
+        for (index, value) in data.iter().skip(2).take(count).enumerate() {
+            let name = match names.get(index) {
+                Some(name) => name.to_string(),
+                None => format!("proc{}", index),
+            };
+            procedures.push((name, parse_i64(value)));
+        }
+    }
+
+    NfsProcStats {
+        version: version.to_string(),
+        procedures,
+    }
+}
+
+// Split the "name: value" lines used by the files of /proc/fs/nfsd/.
+#[inline]
+fn key_value(line: &str) -> Option<(&str, &str)> {
+    line.split_once(':').map(|(key, value)| (key.trim(), value.trim()))
+}
+
+pub fn reply_cache_stats() -> Option<ReplyCacheStats> {
+    let reply_cache_stats = PROC_NFSDV4.to_owned() + "reply_cache_stats";
+
+    if !path_exists(&reply_cache_stats) {
+        return None;
+    }
+
+    let mut stats = ReplyCacheStats::default();
+    let content = wrapper_read(reply_cache_stats);
+    for line in content.iter() {
+        match key_value(line) {
+            Some(("max entries", value)) => stats.max_entries = parse_i64(value),
+            Some(("num entries", value)) => stats.num_entries = parse_i64(value),
+            Some(("hash buckets", value)) => stats.hash_buckets = parse_i64(value),
+            Some(("mem usage", value)) => stats.mem_usage = parse_i64(value),
+            Some(("payload misses", value)) => stats.payload_misses = parse_i64(value),
+            Some(("longest chain len", value)) => stats.longest_chain_len = parse_i64(value),
+            Some(("cachesize at longest", value)) => stats.cachesize_at_longest = parse_i64(value),
+            _ => (),
+        }
+    }
+
+    Some(stats)
+}
+
+// The layout changed with the file cache rework of Linux 6.0, only version 1
+// has "longest chain". Names of both versions are accepted, the missing
+// ones are left at 0.
+pub fn file_cache() -> Option<FileCache> {
+    let filecache = PROC_NFSDV4.to_owned() + "filecache";
+
+    if !path_exists(&filecache) {
+        return None;
     }
 
-    let network_usage = NetworkUsage {
-        netcount: netcount,
-        UDPcount: UDPcount,
-        TCPcount: TCPcount,
-        TCPconnect: TCPconnect,
+    let mut stats = FileCache {
+        format_version: 2,
+        ..FileCache::default()
     };
+    let content = wrapper_read(filecache);
+    for line in content.iter() {
+        match key_value(line) {
+            Some(("total entries", value)) | Some(("total inodes", value)) => stats.total_entries = parse_i64(value),
+            Some(("hash buckets", value)) => stats.hash_buckets = parse_i64(value),
+            Some(("lru entries", value)) => stats.lru_entries = parse_i64(value),
+            Some(("cache hits", value)) => stats.cache_hits = parse_i64(value),
+            Some(("acquisitions", value)) => stats.acquisitions = parse_i64(value),
+            Some(("allocations", value)) => stats.allocations = parse_i64(value),
+            Some(("releases", value)) => stats.releases = parse_i64(value),
+            Some(("evictions", value)) => stats.evictions = parse_i64(value),
+            // "-" until a file was released.
+            Some(("mean age (ms)", value)) => stats.mean_age_ms = parse_i64(value),
+            Some(("longest chain", value)) => {
+                stats.format_version = 1;
+                stats.longest_chain = parse_i64(value);
+            }
+            _ => (),
+        }
+    }
 
-    network_usage
+    Some(stats)
 }
 
-pub fn rpc_nfsd_metrics() -> NfsStats {
-    let mut reply_cache_s = ReplyCache {hits: 0, misses: 0, nocache: 0};
-    let mut io_bytes_s = IOBytes {read: 0, write: 0};
-    let mut network_usage_s = NetworkUsage {netcount: 0, UDPcount: 0, TCPcount: 0, TCPconnect: 0};
-    let mut nfs_stats = NfsStats {reply_cache: reply_cache_s, io_bytes: io_bytes_s, network_usage: network_usage_s};
+// Each line looks like:
+// "<xid> <flags> <prog> NFSv4 COMPOUND <timestamp> <saddr:sport> <daddr:dport> [<ops>...]"
+// Addresses are printed with their port ("192.168.0.1:740",
+// "[fe80::1]:2049"), only the header lists the ports as columns. Fields are
+// located from the NFS version one, so the program number printed by some
+// kernels does not shift them. Operations are printed as "OP_READ", they are
+// named like the proc4ops ones, "read".
+fn parse_rpc_status(content: &[String]) -> Vec<InflightRpc> {
+    let mut requests: Vec<InflightRpc> = Vec::new();
+
+    for line in content.iter().filter(|x| !x.starts_with('#')) {
+        let data: Vec<&str> = line.split_whitespace().collect();
+        let version = match data.iter().position(|x| x.starts_with("NFSv")) {
+            Some(index) => index,
+            None => continue,
+        };
+        let field = |index: usize| data.get(version + index).unwrap_or(&"").to_string();
+        let timestamp = field(2);
+        let timestamp_us = match timestamp.strip_prefix("0x") {
+            Some(hex) => i64::from_str_radix(hex, 16).unwrap_or(0),
+            None => parse_i64(&timestamp),
+        };
+        let (client_address, client_port) = split_address_port(&field(3));
+        let (server_address, server_port) = split_address_port(&field(4));
+
+        requests.push(InflightRpc {
+            xid: data[0].to_string(),
+            flags: data.get(1).unwrap_or(&"").to_string(),
+            version: field(0),
+            procedure: field(1),
+            timestamp_us,
+            client_address,
+            client_port,
+            server_address,
+            server_port,
+            compound_ops: data.iter().skip(version + 5)
+                .map(|x| x.to_lowercase().trim_start_matches("op_").to_string())
+                .collect(),
+        });
+    }
+
+    requests
+}
+
+pub fn rpc_status() -> Vec<InflightRpc> {
+    let rpc_status = PROC_NFSDV4.to_owned() + "rpc_status";
+
+    if path_exists(&rpc_status) {
+        let content = wrapper_read(rpc_status);
+        return parse_rpc_status(&content);
+    }
+
+    Vec::new()
+}
+
+// Operations framing most compounds, SEQUENCE PUTFH READ GETATTR is a read.
+const COMPOUND_FRAMING_OPS: [&str; 6] = ["sequence", "putfh", "putrootfh", "putpubfh", "getattr", "getfh"];
+
+// What a request does: the first operation of the compound that is not
+// framing, or the procedure ("read" for NFSv3, "compound" when the compound
+// only has framing operations).
+pub fn inflight_operation(request: &InflightRpc) -> String {
+    request.compound_ops.iter()
+        .find(|x| !COMPOUND_FRAMING_OPS.contains(&x.as_str()))
+        .unwrap_or(&request.procedure)
+        .to_lowercase()
+}
+
+// Most tunables of /proc/fs/nfsd/ are a single number.
+fn read_number(name: &str) -> i64 {
+    let path = PROC_NFSDV4.to_owned() + name;
+    if path_exists(&path) {
+        let content = wrapper_read(path);
+        return content.first().map(|x| parse_i64(x.trim())).unwrap_or(0);
+    }
+    0
+}
+
+pub fn server_config() -> ServerConfig {
+    let mut config = ServerConfig {
+        threads: read_number("threads"),
+        lease_time: read_number("nfsv4leasetime"),
+        grace_time: read_number("nfsv4gracetime"),
+        max_block_size: read_number("max_block_size"),
+        max_connections: read_number("max_connections"),
+        ..ServerConfig::default()
+    };
+
+    // versions looks like "-2 +3 +4 +4.1 +4.2", keep the enabled ones.
+    let versions = PROC_NFSDV4.to_owned() + "versions";
+    if path_exists(&versions) {
+        let content = wrapper_read(versions);
+        config.versions = content.iter()
+            .flat_map(|x| x.split_whitespace())
+            .filter_map(|x| x.strip_prefix('+'))
+            .map(|x| x.to_string())
+            .collect();
+    }
+
+    // portlist has one "<transport> <port>" line per listening socket.
+    let portlist = PROC_NFSDV4.to_owned() + "portlist";
+    if path_exists(&portlist) {
+        let content = wrapper_read(portlist);
+        config.portlist = content.iter()
+            .map(|x| x.split_whitespace().collect::<Vec<&str>>().join("/"))
+            .collect();
+    }
+
+    if path_exists(SYS_MODULE_NFSD) {
+        let paths = read_dir(SYS_MODULE_NFSD).unwrap();
+        for path in paths {
+            let path = path.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let content = wrapper_read(&path);
+            config.module_parameters.push((name, content.first().map(|x| x.trim().to_string()).unwrap_or_default()));
+        }
+        config.module_parameters.sort();
+    }
+
+    config
+}
+
+// Lines are dispatched on their first field only, every line starts with the
+// name of what it counts.
+fn parse_nfsd_rpc_stats(content: &[String]) -> NfsdRpcStats {
+    let mut stats = NfsdRpcStats::default();
+
+    for line in content.iter() {
+        let data: Vec<&str> = line.split_whitespace().collect();
+        match data.first() {
+            Some(&"rc") => stats.reply_cache = reply_cache(data),
+            Some(&"fh") => stats.file_handle = file_handle(data),
+            Some(&"io") => stats.io_bytes = io_bytes(data),
+            Some(&"th") => stats.threads = threads(data),
+            Some(&"ra") => stats.read_ahead = Some(read_ahead(data)),
+            Some(&"net") => stats.network_usage = network_usage(data),
+            Some(&"rpc") => stats.rpc_calls = rpc_calls(data),
+            Some(&"proc2") => stats.nfsv2_procs = nfs_procs("2", &NFSV2_PROCEDURES, data),
+            Some(&"proc3") => stats.nfsv3_procs = nfs_procs("3", &NFSV3_PROCEDURES, data),
+            Some(&"proc4") => stats.nfsv4_procs = nfs_procs("4", &NFSV4_PROCEDURES, data),
+            Some(&"proc4ops") => stats.nfsv4_ops = nfsv4_ops(data),
+            Some(&"wdeleg_getattr") => stats.wdeleg_getattr = data.get(1).map(|x| parse_i64(x)),
+            Some(key) => stats.unknown.push((
+                key.to_string(),
+                data.iter().skip(1).map(|x| x.to_string()).collect(),
+            )),
+            None => (),
+        }
+    }
 
+    stats
+}
+
+pub fn nfsd_rpc_stats() -> NfsdRpcStats {
     let mut _proc_rpc_nfsd = PROC_RPC.to_owned();
     _proc_rpc_nfsd.push_str("nfsd");
 
     if path_exists(&_proc_rpc_nfsd) {
         let content = wrapper_read(_proc_rpc_nfsd);
+        return parse_nfsd_rpc_stats(&content);
+    }
+
+    NfsdRpcStats::default()
+}
+
+pub fn rpc_nfsd_metrics() -> NfsStats {
+    let stats = nfsd_rpc_stats();
+
+    NfsStats {
+        reply_cache: stats.reply_cache,
+        reply_cache_stats: reply_cache_stats(),
+        io_bytes: stats.io_bytes,
+        network_usage: stats.network_usage,
+        rpc_calls: stats.rpc_calls,
+        threads: stats.threads,
+        nfsv4_ops: stats.nfsv4_ops,
+        nfsv2_procs: stats.nfsv2_procs,
+        nfsv3_procs: stats.nfsv3_procs,
+    }
+}
+
+// pool_stats starts with a header naming the columns, older kernels have an
+// extra overloads-avoided column, so columns are looked up by name.
+pub fn pool_stats() -> Vec<PoolStats> {
+    let mut pools: Vec<PoolStats> = Vec::new();
+    let pool_stats = PROC_NFSDV4.to_owned() + "pool_stats";
+    let pool_threads = PROC_NFSDV4.to_owned() + "pool_threads";
+
+    if path_exists(&pool_stats) {
+        let content = wrapper_read(pool_stats);
+        let mut columns: Vec<String> = Vec::new();
 
         for line in content.iter() {
-            if line.contains("rc") {
-                let rc_data: Vec<&str> = line.split(' ').collect();
-                reply_cache_s = reply_cache(rc_data);
-            }
-            if line.contains("io") {
-                let io_data: Vec<&str> = line.split(' ').collect();
-                io_bytes_s = io_bytes(io_data);
-            }
-            if line.contains("net") {
-                let net_data: Vec<&str> = line.split(' ').collect();
-                network_usage_s = network_usage(net_data);
+            if let Some(header) = line.strip_prefix('#') {
+                columns = header.split_whitespace().map(|x| x.to_string()).collect();
+                continue;
             }
+
+            let data: Vec<&str> = line.split_whitespace().collect();
+            let value = |name: &str| match columns.iter().position(|x| x == name) {
+                Some(index) => data.get(index).map(|x| parse_i64(x)).unwrap_or(0),
+                None => 0,
+            };
+            pools.push(PoolStats {
+                pool: data.first().unwrap_or(&"0").to_string(),
+                packets_arrived: value("packets-arrived"),
+                sockets_enqueued: value("sockets-enqueued"),
+                threads_woken: value("threads-woken"),
+                threads_timedout: value("threads-timedout"),
+                threads: 0,
+            });
         }
+    }
 
-        nfs_stats = NfsStats {
-            reply_cache: reply_cache_s,
-            io_bytes: io_bytes_s,
-            network_usage: network_usage_s,
-        };
+    // pool_threads has the number of threads of each pool, in pool order.
+    if path_exists(&pool_threads) {
+        let content = wrapper_read(pool_threads);
+        let threads: Vec<i64> = content.iter().flat_map(|x| x.split_whitespace()).map(parse_i64).collect();
+        for (pool, count) in pools.iter_mut().zip(threads.iter()) {
+            pool.threads = *count;
+        }
     }
 
-    nfs_stats
+    pools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // /proc/net/rpc/nfsd as printed by Linux 6.6, with the ra line of older
+    // kernels and a line no kernel prints yet.
+    const NFSD_RPC_STATS: &str = "\
+rc 0 2 1143
+fh 0 0 0 0 0
+io 20480 4096
+th 8 0 0.000 0.000 0.000 0.000 0.000 0.000 0.000 0.000 0.000 0.000
+ra 32 0 0 0 0 0 0 0 0 0 0 1
+net 1146 0 1146 5
+rpc 1145 0 0 0 0
+proc3 22 2 10 0 5 0 0 4 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+proc4 2 1 1143
+proc4ops 76 0 0 0 42 0 0 0 0 0 300 0 0 0 0 0 0 0 0 0 0 0 0 1180 0 0 15 0 0 0 0 0 0 0 0 0 0 0 0 7 0 0 0 0 0 0 0 0 0 0 0 0 0 0 1100 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+wdeleg_getattr 3
+future_line 1 2";
+
+    fn sample() -> Vec<String> {
+        NFSD_RPC_STATS.lines().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn procedure_lines_do_not_overwrite_reply_cache() {
+        // "proc3" and "proc4ops" contain "rc", they used to be read as the
+        // reply cache line.
+        let stats = parse_nfsd_rpc_stats(&sample());
+        assert_eq!(stats.reply_cache.hits, 0);
+        assert_eq!(stats.reply_cache.misses, 2);
+        assert_eq!(stats.reply_cache.nocache, 1143);
+    }
+
+    #[test]
+    fn known_lines_are_parsed() {
+        let stats = parse_nfsd_rpc_stats(&sample());
+        assert_eq!((stats.io_bytes.read, stats.io_bytes.write), (20480, 4096));
+        assert_eq!(stats.threads.threads, 8);
+        assert_eq!(stats.read_ahead.map(|x| x.cache_size), Some(32));
+        assert_eq!(stats.network_usage.netcount, 1146);
+        assert_eq!(stats.rpc_calls.calls, 1145);
+        assert_eq!(stats.wdeleg_getattr, Some(3));
+    }
+
+    #[test]
+    fn procedures_and_operations_are_named() {
+        let stats = parse_nfsd_rpc_stats(&sample());
+        assert_eq!(stats.nfsv3_procs.version, "3");
+        assert_eq!(stats.nfsv3_procs.procedures.len(), 22);
+        assert_eq!(stats.nfsv3_procs.procedures[1], ("getattr".to_string(), 10));
+        assert_eq!(stats.nfsv4_procs.procedures, vec![("null".to_string(), 1), ("compound".to_string(), 1143)]);
+
+        let ops = &stats.nfsv4_ops.operations;
+        // the three unassigned operation numbers are skipped.
+        assert_eq!(ops.len(), 73);
+        assert!(ops.contains(&("putfh".to_string(), 1180)));
+        assert!(ops.contains(&("sequence".to_string(), 1100)));
+        assert!(ops.contains(&("write".to_string(), 7)));
+    }
+
+    #[test]
+    fn unknown_lines_are_kept() {
+        let stats = parse_nfsd_rpc_stats(&sample());
+        assert_eq!(stats.unknown, vec![("future_line".to_string(), vec!["1".to_string(), "2".to_string()])]);
+    }
+
+    // /proc/fs/nfsd/rpc_status as printed by Linux 6.7.
+    const RPC_STATUS: &str = "\
+# XID FLAGS VERS PROC TIMESTAMP SADDR SPORT DADDR DPORT COMPOUND_OPS
+5d58666b 000000a1 000186a3 NFSv4 COMPOUND 0000001054812736 192.168.122.1:891 192.168.122.32:2049 OP_SEQUENCE OP_PUTFH OP_READ
+2a5e1c7d 000000a1 000186a3 NFSv4 COMPOUND 0000001054812790 [fe80::1]:740 [fe80::2]:2049 OP_PUTFH OP_READ
+3c1b4d2e 000000a1 000186a3 NFSv4 COMPOUND 0000001054812801 192.168.122.5:702 192.168.122.32:2049 OP_SEQUENCE OP_PUTFH OP_GETATTR
+4f6e2a1b 000000a1 000186a3 NFSv3 WRITE 0000001054812823 192.168.122.7:812 192.168.122.32:2049";
+
+    #[test]
+    fn rpc_status_addresses_hold_their_port() {
+        let requests = parse_rpc_status(&RPC_STATUS.lines().map(|x| x.to_string()).collect::<Vec<String>>());
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].xid, "5d58666b");
+        assert_eq!(requests[0].timestamp_us, 1054812736);
+        assert_eq!((requests[0].client_address.as_str(), requests[0].client_port.as_str()), ("192.168.122.1", "891"));
+        assert_eq!((requests[0].server_address.as_str(), requests[0].server_port.as_str()), ("192.168.122.32", "2049"));
+        assert_eq!(requests[0].compound_ops, vec!["sequence", "putfh", "read"]);
+        // NFSv4.0 compounds have no SEQUENCE, the ops are not taken as addresses.
+        assert_eq!((requests[1].client_address.as_str(), requests[1].client_port.as_str()), ("fe80::1", "740"));
+        assert_eq!(requests[1].compound_ops, vec!["putfh", "read"]);
+        assert!(requests[3].compound_ops.is_empty());
+    }
+
+    #[test]
+    fn rpc_status_operation_skips_framing_ops() {
+        let requests = parse_rpc_status(&RPC_STATUS.lines().map(|x| x.to_string()).collect::<Vec<String>>());
+        let operations: Vec<String> = requests.iter().map(inflight_operation).collect();
+        assert_eq!(operations, vec!["read", "read", "compound", "write"]);
+    }
+
+    #[test]
+    fn missing_lines_keep_defaults() {
+        let stats = parse_nfsd_rpc_stats(&["rc 1 2 3".to_string()]);
+        assert!(stats.read_ahead.is_none());
+        assert!(stats.wdeleg_getattr.is_none());
+        assert!(stats.nfsv4_ops.operations.is_empty());
+    }
 }
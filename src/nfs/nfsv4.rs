@@ -28,74 +28,406 @@
  * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 
-use crate::utils::helper::{path_exists, wrapper_read, PROC_NFSDV4, VAR_NFSDV4};
+use crate::nfs::exports::{device_export, exported_paths, Export, ExportDevice};
+use crate::utils::helper::{path_exists, split_address_port, wrapper_read, PROC_NFSDV4};
+use std::collections::HashMap;
 use std::fs::read_dir;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Nfsv4Client {
     pub clientid: String,
+    // client address and source port, "192.168.0.1:740", "[fe80::1]:740".
     pub address: String,
+    // client owner string, "Linux NFSv4.2 host.example.com".
+    pub name: String,
+    // NFSv4 minor version, "0", "1" or "2".
+    pub minor_version: String,
+    // implementation id sent by NFSv4.1+ clients, the domain, name
+    // ("Linux 6.1.0-13-amd64 ...") and build time.
+    pub implementation_domain: String,
+    pub implementation_name: String,
+    pub implementation_time: String,
+    // state of the callback channel, "UP", "DOWN", "FAULT" or "UNKNOWN".
+    pub callback_state: String,
+    pub callback_address: String,
+    // seconds since the client renewed its lease, Linux 5.19+.
+    pub seconds_from_last_renew: Option<i64>,
+    // "confirmed", "unconfirmed", "courtesy" or "expirable", Linux 5.19+.
+    pub status: String,
     pub ops_count: Nfsv4ClientOps,
+    // entries of the states file, only read with ops.
+    pub states: Vec<Nfsv4State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Nfsv4ClientOps {
     pub t_open: i64,
     pub t_lock: i64,
     pub t_deleg: i64,
     pub t_layout: i64,
+    // opens with write access.
+    pub t_write_open: i64,
+    pub t_read_deleg: i64,
+    pub t_write_deleg: i64,
 }
 
-pub fn number_of_clients() -> i64 {
-    let mut _proc_nfsdv4 = PROC_NFSDV4.to_owned();
-    _proc_nfsdv4.push_str("clients/");
-    if path_exists(&_proc_nfsdv4) {
-        let paths = read_dir(&_proc_nfsdv4).unwrap();
-        return paths.count() as i64;
+// One entry of a client states file.
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone)]
+pub struct Nfsv4State {
+    pub stateid: String,
+    // "open", "lock", "deleg" or "layout".
+    pub state_type: String,
+    // device major and minor numbers and inode of the file.
+    pub major: u64,
+    pub minor: u64,
+    pub inode: u64,
+    pub filename: String,
+    // "r", "w" or "rw" for opens and delegations.
+    pub access: String,
+    // "--", "r", "w" or "rw" for opens.
+    pub deny: String,
+    // open or lock owner, escaped as printed by the kernel.
+    pub owner: String,
+}
+
+// Parts of the `client` label of the per-client metrics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClientLabel {
+    // address and source port as reported, "192.168.0.1:740".
+    Address,
+    // address without the port, "192.168.0.1", "fe80::1".
+    Ip,
+    Clientid,
+    // client owner string, stable across reconnections.
+    Name,
+}
+
+// Parse a comma separated list of label parts, "ip,clientid".
+pub fn parse_client_label(scheme: &str) -> Result<Vec<ClientLabel>, String> {
+    scheme.split(',').map(|x| match x.trim() {
+        "address" => Ok(ClientLabel::Address),
+        "ip" => Ok(ClientLabel::Ip),
+        "clientid" => Ok(ClientLabel::Clientid),
+        "name" => Ok(ClientLabel::Name),
+        other => Err(format!("unknown client label: {}", other)),
+    }).collect()
+}
+
+// Drop the port of "192.168.0.1:740" or "[fe80::1]:740", the brackets of an
+// IPv6 address go with it.
+pub fn address_without_port(address: &str) -> String {
+    split_address_port(address).0
+}
+
+// Value of the `client` label, the parts of `scheme` joined with "/".
+pub fn client_label(client: &Nfsv4Client, scheme: &[ClientLabel]) -> String {
+    scheme.iter().map(|x| match x {
+        ClientLabel::Address => client.address.clone(),
+        ClientLabel::Ip => address_without_port(&client.address),
+        ClientLabel::Clientid => client.clientid.clone(),
+        ClientLabel::Name => client.name.clone(),
+    }).collect::<Vec<String>>().join("/")
+}
+
+// Value of the `client` label of a request from `address` and `port` with no
+// NFSv4 client to take the clientid and name from (NFSv3), the parts that
+// cannot be known are left out.
+pub fn address_label(address: &str, port: &str, scheme: &[ClientLabel]) -> String {
+    let labels: Vec<String> = scheme.iter().filter_map(|x| match x {
+        ClientLabel::Address if address.contains(':') => Some(format!("[{}]:{}", address, port)),
+        ClientLabel::Address => Some(format!("{}:{}", address, port)),
+        ClientLabel::Ip => Some(address.to_string()),
+        ClientLabel::Clientid | ClientLabel::Name => None,
+    }).collect();
+
+    if labels.is_empty() {
+        return address.to_string();
+    }
+    labels.join("/")
+}
+
+// Callback channel states reported by the info files.
+pub const CALLBACK_STATES: [&str; 4] = ["UP", "DOWN", "FAULT", "UNKNOWN"];
+
+// Client statuses reported by the info files since the courteous server
+// (Linux 5.19). Courtesy and expirable clients let their lease expire and
+// are only kept until another client conflicts with their state.
+pub const CLIENT_STATUSES: [&str; 4] = ["confirmed", "unconfirmed", "courtesy", "expirable"];
+
+// Clients holding a lease, older kernels have no status and only list those.
+pub fn number_of_clients(clients: &[Nfsv4Client]) -> i64 {
+    clients.iter()
+        .filter(|x| x.status != "courtesy" && x.status != "expirable")
+        .count() as i64
+}
+
+// etab has one line per export and client, count the exported paths.
+pub fn number_of_exports(exports: &[Export]) -> i64 {
+    exported_paths(exports).len() as i64
+}
+
+// Split "key: value, key: "quoted, value"" on the commas outside quotes.
+fn state_fields(body: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for c in body.chars().chain(std::iter::once(',')) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                if let Some((key, value)) = field.split_once(':') {
+                    fields.push((key.trim().to_string(), value.trim().trim_matches('"').to_string()));
+                }
+                field.clear();
+                continue;
+            },
+            _ => (),
+        }
+        field.push(c);
     }
-    0
+
+    fields
+}
+
+// Superblocks are printed as "fd:10:13649", major and minor in hex and the
+// inode in decimal.
+fn parse_superblock(value: &str) -> Option<(u64, u64, u64)> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    Some((
+        u64::from_str_radix(parts[0], 16).ok()?,
+        u64::from_str_radix(parts[1], 16).ok()?,
+        parts[2].parse::<u64>().ok()?,
+    ))
 }
 
-pub fn number_of_exports() -> i64 {
-    let etab = VAR_NFSDV4.to_owned() + "/etab";
-    if path_exists(&etab) {
-        let content = wrapper_read(etab);
-        return content.len() as i64;
+// The states file has one entry per line:
+// - 0x00000001c4a2e45f2e4d2a6d00000002: { type: open, access: rw, deny: --, superblock: "fd:10:13649", filename: "/srv/file", owner: "open id:\x00..." }
+fn parse_state(line: &str) -> Option<Nfsv4State> {
+    let line = line.trim().strip_prefix("- ")?;
+    let (stateid, body) = line.split_once(": {")?;
+    let body = body.trim_end().strip_suffix('}').unwrap_or(body);
+    let mut state = Nfsv4State {
+        stateid: stateid.trim().to_string(),
+        ..Default::default()
+    };
+
+    for (key, value) in state_fields(body) {
+        match key.as_str() {
+            "type" => state.state_type = value,
+            "superblock" => {
+                if let Some((major, minor, inode)) = parse_superblock(&value) {
+                    state.major = major;
+                    state.minor = minor;
+                    state.inode = inode;
+                }
+            },
+            "filename" => state.filename = value,
+            "access" => state.access = value,
+            "deny" => state.deny = value,
+            "owner" => state.owner = value,
+            _ => (),
+        }
     }
-    0
+
+    Some(state)
 }
 
-fn clients_ops_information(path: &str) -> Nfsv4ClientOps {
+fn states_information(path: &str) -> Vec<Nfsv4State> {
     let clt_states = path.to_owned() + "/states";
-    let (mut t_open, mut t_lock, mut t_deleg, mut t_layout): (i64, i64, i64, i64) = (0, 0, 0, 0);
-
-    if path_exists(&clt_states) {
-        let content = wrapper_read(clt_states);
-
-        for line in content.iter() {
-            if line.contains("type: open") {
-                t_open += 1;
-            } else if line.contains("type: lock") {
-                t_lock += 1;
-            } else if line.contains("type: deleg") {
-                t_deleg += 1;
-            } else if line.contains("type: layout") {
-                t_layout += 1;
+
+    if !path_exists(&clt_states) {
+        return Vec::new();
+    }
+    wrapper_read(clt_states).iter().filter_map(|x| parse_state(x)).collect()
+}
+
+fn clients_ops_information(states: &[Nfsv4State]) -> Nfsv4ClientOps {
+    let mut ops = Nfsv4ClientOps::default();
+
+    for state in states.iter() {
+        match state.state_type.as_str() {
+            "open" => {
+                ops.t_open += 1;
+                ops.t_write_open += state.access.contains('w') as i64;
+            },
+            "lock" => ops.t_lock += 1,
+            "deleg" => {
+                ops.t_deleg += 1;
+                if state.access.contains('w') {
+                    ops.t_write_deleg += 1;
+                } else {
+                    ops.t_read_deleg += 1;
+                }
+            },
+            // The kernel prints only the type, superblock and filename of a
+            // layout, the layout type is not reported.
+            "layout" => ops.t_layout += 1,
+            _ => (),
+        }
+    }
+
+    ops
+}
+
+// Exported filesystem holding a state, from the superblock device. The
+// filename is only the last component of the path, it cannot tell which
+// export of a shared filesystem the state belongs to.
+pub fn state_device<'a>(state: &Nfsv4State, devices: &'a [ExportDevice]) -> Option<&'a ExportDevice> {
+    devices.iter().find(|x| x.major == state.major && x.minor == state.minor)
+}
+
+// Value of the `export` label of a state, see device_export().
+pub fn state_export<'a>(state: &Nfsv4State, devices: &'a [ExportDevice]) -> Option<&'a str> {
+    state_device(state, devices).map(device_export)
+}
+
+// (export, state type, count) across all clients, states on filesystems that
+// are not exported are counted under "unknown". States on a filesystem with
+// several exports are counted under its mount point.
+pub fn states_per_export(clients: &[Nfsv4Client], devices: &[ExportDevice]) -> Vec<(String, String, i64)> {
+    let mut states: Vec<(String, String, i64)> = Vec::new();
+
+    for state in clients.iter().flat_map(|x| x.states.iter()) {
+        let export = state_export(state, devices).unwrap_or("unknown");
+        match states.iter_mut().find(|(x, y, _)| x == export && *y == state.state_type) {
+            Some((_, _, count)) => *count += 1,
+            None => states.push((export.to_string(), state.state_type.clone(), 1)),
+        }
+    }
+
+    states
+}
+
+// (filesystem, number of states) of the exported filesystems a client holds
+// state on, states on filesystems that are not exported are left out.
+pub fn client_exports<'a>(client: &Nfsv4Client, devices: &'a [ExportDevice]) -> Vec<(&'a ExportDevice, i64)> {
+    let mut exports: Vec<(&ExportDevice, i64)> = Vec::new();
+
+    for device in client.states.iter().filter_map(|x| state_device(x, devices)) {
+        match exports.iter_mut().find(|(x, _)| x.major == device.major && x.minor == device.minor) {
+            Some((_, count)) => *count += 1,
+            None => exports.push((device, 1)),
+        }
+    }
+
+    exports
+}
+
+// Open and lock states held on one file across all clients.
+#[derive(Debug, Default, Clone)]
+pub struct HotFile {
+    pub export: String,
+    // last component of the path, the inode tells apart files of the same
+    // name.
+    pub filename: String,
+    pub major: u64,
+    pub minor: u64,
+    pub inode: u64,
+    pub opens: i64,
+    pub locks: i64,
+    // clients holding an open or a lock on the file.
+    pub clients: i64,
+}
+
+// Files with open or lock states, the most opened first.
+pub fn hot_files(clients: &[Nfsv4Client], devices: &[ExportDevice]) -> Vec<HotFile> {
+    let mut files: HashMap<(u64, u64, u64), HotFile> = HashMap::new();
+
+    for client in clients.iter() {
+        let mut seen: Vec<(u64, u64, u64)> = Vec::new();
+        for state in client.states.iter().filter(|x| x.state_type == "open" || x.state_type == "lock") {
+            let key = (state.major, state.minor, state.inode);
+            let file = files.entry(key).or_insert_with(|| HotFile {
+                export: state_export(state, devices).unwrap_or("unknown").to_string(),
+                filename: state.filename.clone(),
+                major: state.major,
+                minor: state.minor,
+                inode: state.inode,
+                ..Default::default()
+            });
+            if state.state_type == "open" {
+                file.opens += 1;
+            } else {
+                file.locks += 1;
+            }
+            if !seen.contains(&key) {
+                seen.push(key);
+                file.clients += 1;
             }
         }
     }
 
-    let nfsv4_client_ops = Nfsv4ClientOps {
-        t_open: t_open,
-        t_lock: t_lock,
-        t_deleg: t_deleg,
-        t_layout: t_layout,
-    };
+    let mut files: Vec<HotFile> = files.into_values().collect();
+    // ties are broken on the file so the --hot-files cut-off is stable.
+    files.sort_by(|x, y| {
+        y.opens.cmp(&x.opens)
+            .then(y.locks.cmp(&x.locks))
+            .then((x.major, x.minor, x.inode).cmp(&(y.major, y.minor, y.inode)))
+    });
+    files
+}
 
-    nfsv4_client_ops
+// The `n` files holding the most locks.
+pub fn most_locked_files(files: &[HotFile], n: usize) -> Vec<HotFile> {
+    let mut files: Vec<HotFile> = files.iter().filter(|x| x.locks > 0).cloned().collect();
+    files.sort_by(|x, y| {
+        y.locks.cmp(&x.locks)
+            .then(y.opens.cmp(&x.opens))
+            .then((x.major, x.minor, x.inode).cmp(&(y.major, y.minor, y.inode)))
+    });
+    files.truncate(n);
+    files
 }
 
-pub fn clients_information() -> Vec<Nfsv4Client> {
+// The `n` files holding the most opens.
+pub fn most_opened_files(files: &[HotFile], n: usize) -> Vec<HotFile> {
+    files.iter().filter(|x| x.opens > 0).take(n).cloned().collect()
+}
+
+// The info file has one "name: value" line per field, the strings are
+// quoted:
+// clientid: 0x6d0a5ac26357e51f
+// address: "192.168.122.1:729"
+// name: "Linux NFSv4.2 host"
+// minor version: 2
+fn client_info(content: &[String]) -> Nfsv4Client {
+    let mut client = Nfsv4Client::default();
+
+    for line in content.iter() {
+        let (key, value) = match line.split_once(':') {
+            Some((key, value)) => (key.trim(), value.trim().trim_matches('"').to_string()),
+            None => continue,
+        };
+        match key {
+            "clientid" => client.clientid = value,
+            "address" => client.address = value,
+            "name" => client.name = value,
+            "minor version" => client.minor_version = value,
+            "Implementation domain" => client.implementation_domain = value,
+            "Implementation name" => client.implementation_name = value,
+            "Implementation time" => client.implementation_time = value,
+            "callback state" => client.callback_state = value,
+            "callback address" => client.callback_address = value,
+            "seconds from last renew" => client.seconds_from_last_renew = value.parse::<i64>().ok(),
+            "status" => client.status = value,
+            _ => (),
+        }
+    }
+
+    client
+}
+
+// Reading the states file of every client can be CPU intensive, it is only
+// done when `with_ops` is set.
+pub fn clients_information(with_ops: bool) -> Vec<Nfsv4Client> {
     let mut nfsv4_client: Vec<Nfsv4Client> = Vec::new();
     let mut _proc_nfsdv4_clients = PROC_NFSDV4.to_owned();
 
@@ -103,30 +435,72 @@ pub fn clients_information() -> Vec<Nfsv4Client> {
     if path_exists(&_proc_nfsdv4_clients) {
         let paths = read_dir(&_proc_nfsdv4_clients).unwrap();
         for path in paths {
-            let mut clientid = String::new();
-            let mut address = String::new();
             let _path = path.unwrap().path();
             let info = _path.to_str().unwrap().to_owned() + "/info";
 
             let content = wrapper_read(info);
-
-            for line in content.iter() {
-                let mut line = line.to_owned();
-                if line.contains("clientid") {
-                    clientid = line.replace("clientid:", "").trim().to_string();
-                }
-                if line.contains("address") && !line.contains("callback") {
-                    line.retain(|x| !['\"'].contains(&x));
-                    address = line.replace("address:", "").trim().to_string();
-                }
+            let mut client = client_info(&content);
+            if with_ops {
+                client.states = states_information(_path.to_str().unwrap());
+                client.ops_count = clients_ops_information(&client.states);
             }
-            nfsv4_client.push(Nfsv4Client {
-                clientid: clientid,
-                address: address,
-                ops_count: clients_ops_information(&_path.to_str().unwrap()),
-            });
+            nfsv4_client.push(client);
         }
     }
 
     nfsv4_client
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lines of /proc/fs/nfsd/clients/*/states, owners are quoted with \x..
+    // escapes and filenames may hold commas and quotes.
+    const OPEN: &str = r#"- 0x00000001c4a2e45f2e4d2a6d00000002: { type: open, access: rw, deny: --, superblock: "fd:10:13649", filename: "a, \"b\".txt", owner: "open id:\x00\x00\x00\x2c,\x00" }"#;
+    const DELEG: &str = r#"- 0x00000001c4a2e45f2e4d2a6d00000003: { type: deleg, access: r, superblock: "103:02:1234", filename: "x" }"#;
+    const LOCK: &str = r#"- 0x00000001c4a2e45f2e4d2a6d00000004: { type: lock, superblock: "fd:10:13649", filename: "a", owner: "lock id:\x1a\x00" }"#;
+    const LAYOUT: &str = r#"- 0x00000001c4a2e45f2e4d2a6d00000005: { type: layout, superblock: "fd:10:1", filename: "y" }"#;
+
+    #[test]
+    fn quoted_values_keep_their_commas() {
+        let fields = state_fields(r#"type: open, filename: "a, b", owner: "x\",y""#);
+        assert_eq!(fields, vec![
+            ("type".to_string(), "open".to_string()),
+            ("filename".to_string(), "a, b".to_string()),
+            ("owner".to_string(), r#"x\",y"#.to_string()),
+        ]);
+    }
+
+    #[test]
+    fn open_state_is_parsed() {
+        let state = parse_state(OPEN).unwrap();
+        assert_eq!(state.stateid, "0x00000001c4a2e45f2e4d2a6d00000002");
+        assert_eq!(state.state_type, "open");
+        assert_eq!((state.major, state.minor, state.inode), (0xfd, 0x10, 13649));
+        assert_eq!(state.filename, r#"a, \"b\".txt"#);
+        assert_eq!((state.access.as_str(), state.deny.as_str()), ("rw", "--"));
+        assert_eq!(state.owner, r#"open id:\x00\x00\x00\x2c,\x00"#);
+    }
+
+    #[test]
+    fn superblock_numbers_are_hexadecimal() {
+        let state = parse_state(DELEG).unwrap();
+        assert_eq!((state.major, state.minor, state.inode), (0x103, 0x02, 1234));
+    }
+
+    #[test]
+    fn other_lines_are_skipped() {
+        assert!(parse_state("").is_none());
+        assert!(parse_state("states of client 0x6d0a5ac26357e51f").is_none());
+    }
+
+    #[test]
+    fn states_are_counted_by_type_and_mode() {
+        let states: Vec<Nfsv4State> = [OPEN, DELEG, LOCK, LAYOUT].iter().filter_map(|x| parse_state(x)).collect();
+        let ops = clients_ops_information(&states);
+        assert_eq!((ops.t_open, ops.t_lock, ops.t_deleg, ops.t_layout), (1, 1, 1, 1));
+        assert_eq!(ops.t_write_open, 1);
+        assert_eq!((ops.t_read_deleg, ops.t_write_deleg), (1, 0));
+    }
+}
@@ -30,26 +30,55 @@
 
 use clap::ArgMatches;
 use prometheus::{
-    IntGauge, IntGaugeVec, Opts, Registry,
+    Gauge, GaugeVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
 };
+use serde_json::json;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::result::Result;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use warp::{Filter, Rejection, Reply};
 
-use crate::nfs::nfs_generic::rpc_nfsd_metrics;
-use crate::nfs::nfsv4::{clients_information, number_of_clients, number_of_exports};
+use crate::nfs::nfs_generic::{
+    file_cache, inflight_operation, pool_stats, rpc_nfsd_metrics, rpc_status, server_config,
+};
+use crate::nfs::exports::{
+    configured_exports, device_export, etab_exports, export_risks, exports_capacity, exports_drift, exports_stats,
+    exports_devices, exports_diskstats, probe_exports,
+};
+use crate::nfs::nfsv4::{
+    address_label, client_exports, client_label, clients_information, hot_files, most_locked_files, most_opened_files,
+    number_of_clients, number_of_exports, parse_client_label, states_per_export, ClientLabel, HotFile,
+    CALLBACK_STATES, CLIENT_STATUSES,
+};
+use crate::utils::helper::monotonic_us;
+use crate::utils::mountinfo::mountinfo;
 
 // Enable or Disable metrics, disable by default nfsv4 ops per clients
 #[derive(Debug, Clone)]
 pub struct ExporterOptions {
     nfsv4_ops_clients: bool,
+    // exported paths that must only be mounted with Kerberos.
+    kerberos_only_exports: Vec<String>,
+    // time given to stat() an exported path before reporting it hung.
+    export_probe_timeout: Duration,
+    // how per-client metrics are labelled.
+    client_label: Vec<ClientLabel>,
+    // number of most opened and most locked files reported.
+    hot_files: usize,
 }
 
 lazy_static! {
     // Export options
-    static ref EXPORTEROPTS: Mutex<ExporterOptions> = Mutex::new(ExporterOptions { nfsv4_ops_clients: false });
+    static ref EXPORTEROPTS: Mutex<ExporterOptions> = Mutex::new(ExporterOptions {
+        nfsv4_ops_clients: false,
+        kerberos_only_exports: Vec::new(),
+        export_probe_timeout: Duration::from_secs(5),
+        client_label: vec![ClientLabel::Address],
+        hot_files: 10,
+    });
 
     pub static ref REGISTRY: Registry = Registry::new();
     // Number of clients connected
@@ -62,6 +91,144 @@ lazy_static! {
         IntGauge::new("nfsv4_exports_total", "Number of NFSv4 exports")
             .expect("metric can be created");
 
+    // Export inventory
+    pub static ref EXPORT_INFO: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_info", "Active export and its options per client specification, always 1"),
+        &["export_path", "client_spec", "access", "sync", "root_squash", "all_squash", "secure", "subtree_check", "sec", "fsid"])
+            .expect("metric can be created");
+
+    // Export security audit
+    pub static ref EXPORT_RISK: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_risk", "1 when an exported path breaks a security rule, 0 otherwise"),
+        &["path", "rule"])
+            .expect("metric can be created");
+
+    // Drift between /etc/exports and etab
+    pub static ref EXPORTS_DRIFT: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_exports_drift", "Export differing between /etc/exports and etab (added, removed or changed), always 1"),
+        &["export_path", "client_spec", "state"])
+            .expect("metric can be created");
+    pub static ref EXPORTS_IN_SYNC: IntGauge =
+        IntGauge::new("nfsd_exports_in_sync", "1 when the active exports match /etc/exports, 0 otherwise")
+            .expect("metric can be created");
+
+    // Health of the storage behind the exported paths
+    pub static ref EXPORT_PROBE_SUCCESS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_probe_success", "1 when the exported path could be stat'ed before the timeout, 0 otherwise"),
+        &["export_path"])
+            .expect("metric can be created");
+    pub static ref EXPORT_PROBE_DURATION: GaugeVec =
+        GaugeVec::new(Opts::new("nfsd_export_probe_duration_seconds", "Time taken to stat the exported path"),
+        &["export_path"])
+            .expect("metric can be created");
+
+    // Filesystem capacity of the exported paths
+    pub static ref EXPORT_SIZE_BYTES: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_size_bytes", "Size of the filesystem holding the exported path"),
+        &["export_path", "fstype"])
+            .expect("metric can be created");
+    pub static ref EXPORT_FREE_BYTES: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_free_bytes", "Free space of the filesystem holding the exported path"),
+        &["export_path", "fstype"])
+            .expect("metric can be created");
+    pub static ref EXPORT_AVAIL_BYTES: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_avail_bytes", "Space available to non-root users on the filesystem holding the exported path"),
+        &["export_path", "fstype"])
+            .expect("metric can be created");
+    pub static ref EXPORT_FILES: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_files", "Number of inodes of the filesystem holding the exported path"),
+        &["export_path", "fstype"])
+            .expect("metric can be created");
+    pub static ref EXPORT_FILES_FREE: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_files_free", "Number of free inodes of the filesystem holding the exported path"),
+        &["export_path", "fstype"])
+            .expect("metric can be created");
+
+    // Block device I/O statistics of the exported paths
+    pub static ref EXPORT_DEVICE_IOS_IN_PROGRESS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_export_device_ios_in_progress", "Number of I/Os in progress on the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_READS_COMPLETED: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_reads_completed_total", "Number of reads completed by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_READS_MERGED: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_reads_merged_total", "Number of reads merged by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_SECTORS_READ: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_sectors_read_total", "Number of sectors read from the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_READ_TIME: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_read_time_milliseconds_total", "Time spent reading by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_WRITES_COMPLETED: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_writes_completed_total", "Number of writes completed by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_WRITES_MERGED: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_writes_merged_total", "Number of writes merged by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_SECTORS_WRITTEN: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_sectors_written_total", "Number of sectors written to the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_WRITE_TIME: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_write_time_milliseconds_total", "Time spent writing by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_IO_TIME: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_io_time_milliseconds_total", "Time spent doing I/Os by the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+    pub static ref EXPORT_DEVICE_WEIGHTED_IO_TIME: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_device_io_time_weighted_milliseconds_total", "Time I/Os spent in queue on the block device holding the exported path"),
+        &["export_path", "device"])
+            .expect("metric can be created");
+
+    // Per-export statistics
+    pub static ref EXPORT_READ_BYTES: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_read_bytes_total", "Total of bytes read per export"),
+        &["export_path", "client_spec"])
+            .expect("metric can be created");
+    pub static ref EXPORT_WRITE_BYTES: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_write_bytes_total", "Total of bytes written per export"),
+        &["export_path", "client_spec"])
+            .expect("metric can be created");
+    pub static ref EXPORT_STALE_FILEHANDLES: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_export_stale_filehandles_total", "Number of stale file handles returned per export"),
+        &["export_path", "client_spec"])
+            .expect("metric can be created");
+
+    // NFSv4 clients
+    pub static ref NFSV4_CLIENT_INFO: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_client_info", "NFSv4 client information, always 1"),
+        &["client", "clientid", "name", "minor_version", "implementation_domain", "implementation_name",
+          "implementation_time", "callback_state", "callback_address", "status"])
+            .expect("metric can be created");
+
+    pub static ref NFSV4_CLIENT_CALLBACK_STATE: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_client_callback_state", "1 for the current callback channel state of the NFSv4 client, 0 for the others"),
+        &["client", "state"])
+            .expect("metric can be created");
+    pub static ref NFSV4_CLIENTS_BY_CALLBACK_STATE: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_clients_by_callback_state", "Number of NFSv4 clients per callback channel state"),
+        &["state"])
+            .expect("metric can be created");
+
+    pub static ref NFSV4_CLIENTS_BY_STATUS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_clients_by_status", "Number of NFSv4 clients per status"),
+        &["status"])
+            .expect("metric can be created");
+    pub static ref NFSV4_CLIENT_IDLE_SECONDS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_client_idle_seconds", "Seconds since the NFSv4 client renewed its lease"),
+        &["client"])
+            .expect("metric can be created");
+
     // Number of FS OPS per client
     pub static ref OPEN_PER_NFSV4_CLIENT: IntGaugeVec =
         IntGaugeVec::new(Opts::new("nfsv4_op_open_per_client", "Number of open operations per NFSv4 client"),
@@ -79,6 +246,31 @@ lazy_static! {
         IntGaugeVec::new(Opts::new("nfsv4_op_layout_per_client", "Number of layout operations per NFSv4 client"),
         &["client"])
             .expect("metric can be created");
+    pub static ref NFSV4_STATES: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_states", "Number of NFSv4 states (open, lock, deleg, layout) per export across all clients"),
+        &["export", "type"])
+            .expect("metric can be created");
+    pub static ref NFSV4_CLIENT_EXPORT_ACTIVE: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_client_export_active", "1 when the NFSv4 client holds state on the export"),
+        &["client", "export"])
+            .expect("metric can be created");
+    // Most opened and most locked files, --hot-files of each.
+    pub static ref NFSV4_HOT_FILE_OPENS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_hot_file_opens", "Number of NFSv4 opens of the most opened files across all clients"),
+        &["export", "filename", "inode"])
+            .expect("metric can be created");
+    pub static ref NFSV4_HOT_FILE_LOCKS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_hot_file_locks", "Number of NFSv4 locks of the most locked files across all clients"),
+        &["export", "filename", "inode"])
+            .expect("metric can be created");
+    pub static ref WRITE_OPEN_PER_NFSV4_CLIENT: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_op_write_open_per_client", "Number of opens with write access per NFSv4 client"),
+        &["client"])
+            .expect("metric can be created");
+    pub static ref DELEG_PER_NFSV4_CLIENT_BY_TYPE: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsv4_op_deleg_per_client_by_type", "Number of read and write delegations per NFSv4 client"),
+        &["client", "type"])
+            .expect("metric can be created");
 
     // Cache
     pub static ref REPLY_CACHE_HITS: IntGauge =
@@ -90,6 +282,24 @@ lazy_static! {
     pub static ref REPLY_CACHE_NOCACHE: IntGauge =
         IntGauge::new("nfs_reply_cache_nocache", "Number of nocache")
             .expect("metric can be created");
+    pub static ref REPLY_CACHE_MAX_ENTRIES: IntGauge =
+        IntGauge::new("nfs_reply_cache_max_entries", "Maximum number of entries of the reply cache")
+            .expect("metric can be created");
+    pub static ref REPLY_CACHE_ENTRIES: IntGauge =
+        IntGauge::new("nfs_reply_cache_entries", "Number of entries in the reply cache")
+            .expect("metric can be created");
+    pub static ref REPLY_CACHE_HASH_BUCKETS: IntGauge =
+        IntGauge::new("nfs_reply_cache_hash_buckets", "Number of hash buckets of the reply cache")
+            .expect("metric can be created");
+    pub static ref REPLY_CACHE_MEMORY: IntGauge =
+        IntGauge::new("nfs_reply_cache_memory_bytes", "Memory used by the reply cache")
+            .expect("metric can be created");
+    pub static ref REPLY_CACHE_PAYLOAD_MISSES: IntCounter =
+        IntCounter::new("nfs_reply_cache_payload_misses_total", "Number of replies too large to be cached")
+            .expect("metric can be created");
+    pub static ref REPLY_CACHE_LONGEST_CHAIN: IntGauge =
+        IntGauge::new("nfs_reply_cache_longest_chain_length", "Length of the longest hash chain of the reply cache")
+            .expect("metric can be created");
 
     // IOBytes read and write
     pub static ref IOBYTES_READ: IntGauge =
@@ -112,6 +322,148 @@ lazy_static! {
     pub static ref NETWORK_CONNECTIONS: IntGauge =
         IntGauge::new("nfs_network_connections", "Total amount of network connections")
             .expect("metric can be created");
+
+    // RPC calls
+    pub static ref RPC_CALLS: IntCounter =
+        IntCounter::new("nfsd_rpc_calls_total", "Total amount of RPC calls")
+            .expect("metric can be created");
+    pub static ref RPC_BADCALLS: IntCounter =
+        IntCounter::new("nfsd_rpc_badcalls_total", "Total amount of rejected RPC calls")
+            .expect("metric can be created");
+    pub static ref RPC_BADFMT: IntCounter =
+        IntCounter::new("nfsd_rpc_badfmt_total", "Total amount of RPC calls with a malformed header")
+            .expect("metric can be created");
+    pub static ref RPC_BADAUTH: IntCounter =
+        IntCounter::new("nfsd_rpc_badauth_total", "Total amount of RPC calls with invalid credentials")
+            .expect("metric can be created");
+    pub static ref RPC_BADCLNT: IntCounter =
+        IntCounter::new("nfsd_rpc_badclnt_total", "Total amount of RPC calls from unknown clients")
+            .expect("metric can be created");
+
+    // Threads
+    pub static ref THREADS: IntGauge =
+        IntGauge::new("nfsd_threads", "Number of nfsd threads")
+            .expect("metric can be created");
+    pub static ref THREADS_ALL_BUSY: IntCounter =
+        IntCounter::new("nfsd_threads_all_busy_total", "Number of times all nfsd threads were busy")
+            .expect("metric can be created");
+    pub static ref POOL_THREADS: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_pool_threads", "Number of nfsd threads per pool"),
+        &["pool"])
+            .expect("metric can be created");
+    pub static ref POOL_PACKETS_ARRIVED: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_pool_packets_arrived_total", "Total amount of packets received per pool"),
+        &["pool"])
+            .expect("metric can be created");
+    pub static ref POOL_SOCKETS_ENQUEUED: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_pool_sockets_enqueued_total", "Number of times a socket was queued because no thread was free per pool"),
+        &["pool"])
+            .expect("metric can be created");
+    pub static ref POOL_THREADS_WOKEN: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_pool_threads_woken_total", "Number of times an idle thread was woken up per pool"),
+        &["pool"])
+            .expect("metric can be created");
+    pub static ref POOL_THREADS_TIMEDOUT: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_pool_threads_timedout_total", "Number of times a thread timed out waiting for work per pool"),
+        &["pool"])
+            .expect("metric can be created");
+
+    // File cache
+    pub static ref FILECACHE_FORMAT_VERSION: IntGauge =
+        IntGauge::new("nfsd_filecache_format_version", "Layout version of the nfsd file cache statistics")
+            .expect("metric can be created");
+    pub static ref FILECACHE_ENTRIES: IntGauge =
+        IntGauge::new("nfsd_filecache_entries", "Number of files in the nfsd file cache")
+            .expect("metric can be created");
+    pub static ref FILECACHE_HASH_BUCKETS: IntGauge =
+        IntGauge::new("nfsd_filecache_hash_buckets", "Number of hash buckets of the nfsd file cache")
+            .expect("metric can be created");
+    pub static ref FILECACHE_LRU_ENTRIES: IntGauge =
+        IntGauge::new("nfsd_filecache_lru_entries", "Number of files on the nfsd file cache LRU")
+            .expect("metric can be created");
+    pub static ref FILECACHE_LONGEST_CHAIN: IntGauge =
+        IntGauge::new("nfsd_filecache_longest_chain_length", "Length of the longest hash chain of the nfsd file cache")
+            .expect("metric can be created");
+    pub static ref FILECACHE_HITS: IntCounter =
+        IntCounter::new("nfsd_filecache_hits_total", "Number of nfsd file cache hits")
+            .expect("metric can be created");
+    pub static ref FILECACHE_ACQUISITIONS: IntCounter =
+        IntCounter::new("nfsd_filecache_acquisitions_total", "Number of nfsd file cache lookups")
+            .expect("metric can be created");
+    pub static ref FILECACHE_ALLOCATIONS: IntCounter =
+        IntCounter::new("nfsd_filecache_allocations_total", "Number of files added to the nfsd file cache")
+            .expect("metric can be created");
+    pub static ref FILECACHE_RELEASES: IntCounter =
+        IntCounter::new("nfsd_filecache_releases_total", "Number of files released from the nfsd file cache")
+            .expect("metric can be created");
+    pub static ref FILECACHE_EVICTIONS: IntCounter =
+        IntCounter::new("nfsd_filecache_evictions_total", "Number of files evicted from the nfsd file cache")
+            .expect("metric can be created");
+    pub static ref FILECACHE_MEAN_AGE: IntGauge =
+        IntGauge::new("nfsd_filecache_mean_age_milliseconds", "Mean age of the files released from the nfsd file cache")
+            .expect("metric can be created");
+
+    // In-flight RPC requests
+    pub static ref RPC_INFLIGHT: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_rpc_inflight", "Number of RPC requests being processed by operation"),
+        &["operation"])
+            .expect("metric can be created");
+    pub static ref RPC_INFLIGHT_PER_CLIENT: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_rpc_inflight_per_client", "Number of RPC requests being processed per client"),
+        &["client"])
+            .expect("metric can be created");
+    pub static ref RPC_OLDEST_INFLIGHT: Gauge =
+        Gauge::new("nfsd_rpc_oldest_inflight_seconds", "Age of the oldest RPC request being processed")
+            .expect("metric can be created");
+
+    // Server configuration
+    pub static ref SERVER_INFO: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_server_info", "nfsd configuration, always 1"),
+        &["versions", "portlist"])
+            .expect("metric can be created");
+    pub static ref CONFIG_THREADS: IntGauge =
+        IntGauge::new("nfsd_config_threads", "Number of nfsd threads requested")
+            .expect("metric can be created");
+    pub static ref CONFIG_LEASE_TIME: IntGauge =
+        IntGauge::new("nfsd_config_lease_time_seconds", "NFSv4 lease time")
+            .expect("metric can be created");
+    pub static ref CONFIG_GRACE_TIME: IntGauge =
+        IntGauge::new("nfsd_config_grace_time_seconds", "NFSv4 grace time")
+            .expect("metric can be created");
+    pub static ref CONFIG_MAX_BLOCK_SIZE: IntGauge =
+        IntGauge::new("nfsd_config_max_block_size_bytes", "Largest read or write size")
+            .expect("metric can be created");
+    pub static ref CONFIG_MAX_CONNECTIONS: IntGauge =
+        IntGauge::new("nfsd_config_max_connections", "Maximum number of connections, 0 means no limit")
+            .expect("metric can be created");
+    pub static ref MODULE_PARAMETER_INFO: IntGaugeVec =
+        IntGaugeVec::new(Opts::new("nfsd_module_parameter_info", "nfsd module parameters, always 1"),
+        &["parameter", "value"])
+            .expect("metric can be created");
+
+    // NFSv4 operations
+    pub static ref NFSV4_OPERATIONS: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_v4_operations_total", "Number of NFSv4 operations by operation"),
+        &["op"])
+            .expect("metric can be created");
+
+    // NFSv2 and NFSv3 procedures
+    pub static ref NFS_PROCEDURES: IntCounterVec =
+        IntCounterVec::new(Opts::new("nfsd_procedures_total", "Number of NFSv2 and NFSv3 procedures by version and procedure"),
+        &["version", "procedure"])
+            .expect("metric can be created");
+}
+
+// Counters cannot be set, so move them forward by the difference with the
+// value read last time. A smaller value means nfsd counters were reset.
+fn set_counter(counter: &IntCounter, value: i64) {
+    let current = counter.get();
+    if value < current {
+        counter.reset();
+        counter.inc_by(value);
+    } else {
+        counter.inc_by(value - current);
+    }
 }
 
 fn register_metrics() {
@@ -119,6 +471,66 @@ fn register_metrics() {
         .expect("collector can be registered");
     REGISTRY.register(Box::new(NUMBER_OF_NFSV4_EXPORTS.clone()))
         .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_INFO.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_RISK.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORTS_DRIFT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORTS_IN_SYNC.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_PROBE_SUCCESS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_PROBE_DURATION.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_SIZE_BYTES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_FREE_BYTES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_AVAIL_BYTES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_FILES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_FILES_FREE.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_IOS_IN_PROGRESS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_READS_COMPLETED.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_READS_MERGED.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_SECTORS_READ.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_READ_TIME.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_WRITES_COMPLETED.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_WRITES_MERGED.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_SECTORS_WRITTEN.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_WRITE_TIME.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_IO_TIME.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_DEVICE_WEIGHTED_IO_TIME.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_READ_BYTES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_WRITE_BYTES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(EXPORT_STALE_FILEHANDLES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_CLIENT_INFO.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_CLIENT_CALLBACK_STATE.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_CLIENTS_BY_CALLBACK_STATE.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_CLIENTS_BY_STATUS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_CLIENT_IDLE_SECONDS.clone()))
+        .expect("collector can be registered");
     REGISTRY.register(Box::new(OPEN_PER_NFSV4_CLIENT.clone()))
         .expect("collector can be registered");
     REGISTRY.register(Box::new(LOCK_PER_NFSV4_CLIENT.clone()))
@@ -127,12 +539,36 @@ fn register_metrics() {
         .expect("collector can be registered");
     REGISTRY.register(Box::new(LAYOUT_PER_NFSV4_CLIENT.clone()))
         .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_STATES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_CLIENT_EXPORT_ACTIVE.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_HOT_FILE_OPENS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_HOT_FILE_LOCKS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(WRITE_OPEN_PER_NFSV4_CLIENT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(DELEG_PER_NFSV4_CLIENT_BY_TYPE.clone()))
+        .expect("collector can be registered");
     REGISTRY.register(Box::new(REPLY_CACHE_HITS.clone()))
         .expect("collector can be registered");
     REGISTRY.register(Box::new(REPLY_CACHE_MISSES.clone()))
         .expect("collector can be registered");
     REGISTRY.register(Box::new(REPLY_CACHE_NOCACHE.clone()))
         .expect("collector can be registered");
+    REGISTRY.register(Box::new(REPLY_CACHE_MAX_ENTRIES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(REPLY_CACHE_ENTRIES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(REPLY_CACHE_HASH_BUCKETS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(REPLY_CACHE_MEMORY.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(REPLY_CACHE_PAYLOAD_MISSES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(REPLY_CACHE_LONGEST_CHAIN.clone()))
+        .expect("collector can be registered");
     REGISTRY.register(Box::new(IOBYTES_READ.clone()))
         .expect("collector can be registered");
     REGISTRY.register(Box::new(IOBYTES_WRITE.clone()))
@@ -145,6 +581,76 @@ fn register_metrics() {
         .expect("collector can be registered");
     REGISTRY.register(Box::new(NETWORK_CONNECTIONS.clone()))
         .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_CALLS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_BADCALLS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_BADFMT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_BADAUTH.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_BADCLNT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(THREADS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(THREADS_ALL_BUSY.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(POOL_THREADS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(POOL_PACKETS_ARRIVED.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(POOL_SOCKETS_ENQUEUED.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(POOL_THREADS_WOKEN.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(POOL_THREADS_TIMEDOUT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_FORMAT_VERSION.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_ENTRIES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_HASH_BUCKETS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_LRU_ENTRIES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_LONGEST_CHAIN.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_HITS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_ACQUISITIONS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_ALLOCATIONS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_RELEASES.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_EVICTIONS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(FILECACHE_MEAN_AGE.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_INFLIGHT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_INFLIGHT_PER_CLIENT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(RPC_OLDEST_INFLIGHT.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(SERVER_INFO.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(CONFIG_THREADS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(CONFIG_LEASE_TIME.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(CONFIG_GRACE_TIME.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(CONFIG_MAX_BLOCK_SIZE.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(CONFIG_MAX_CONNECTIONS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(MODULE_PARAMETER_INFO.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFSV4_OPERATIONS.clone()))
+        .expect("collector can be registered");
+    REGISTRY.register(Box::new(NFS_PROCEDURES.clone()))
+        .expect("collector can be registered");
 }
 
 // Index handler.
@@ -152,32 +658,286 @@ async fn index_handler() -> Result<impl Reply, Rejection> {
     Ok("")
 }
 
+fn hot_files_json(files: &[HotFile]) -> serde_json::Value {
+    files.iter().map(|x| json!({
+        "export": x.export,
+        "filename": x.filename,
+        "device": format!("{}:{}", x.major, x.minor),
+        "inode": x.inode,
+        "opens": x.opens,
+        "locks": x.locks,
+        "clients": x.clients,
+    })).collect()
+}
+
+// Most opened and most locked files, the states files are read on every
+// request whatever --nfsv4-ops-clients is.
+async fn hot_files_handler() -> Result<impl Reply, Rejection> {
+    let opts = EXPORTEROPTS.lock().unwrap().clone();
+    let clients = clients_information(true);
+    let devices = exports_devices(&etab_exports(), &mountinfo());
+    let files = hot_files(&clients, &devices);
+
+    Ok(warp::reply::json(&json!({
+        "opens": hot_files_json(&most_opened_files(&files, opts.hot_files)),
+        "locks": hot_files_json(&most_locked_files(&files, opts.hot_files)),
+    })))
+}
+
+// Exports every client holds state on with the number of states, the states
+// files are read on every request whatever --nfsv4-ops-clients is. States
+// on a filesystem with several exports are listed under its mount point with
+// every export as a candidate.
+async fn client_exports_handler() -> Result<impl Reply, Rejection> {
+    let opts = EXPORTEROPTS.lock().unwrap().clone();
+    let clients = clients_information(true);
+    let devices = exports_devices(&etab_exports(), &mountinfo());
+
+    let usage: Vec<serde_json::Value> = clients.iter().map(|x| json!({
+        "client": client_label(x, &opts.client_label),
+        "clientid": x.clientid,
+        "address": x.address,
+        "name": x.name,
+        "exports": client_exports(x, &devices).iter()
+            .map(|(device, states)| json!({
+                "export": device_export(device),
+                "mount_point": device.mount_point,
+                "candidates": device.exports,
+                "ambiguous": device.exports.len() > 1,
+                "states": states,
+            }))
+            .collect::<Vec<serde_json::Value>>(),
+    })).collect();
+
+    Ok(warp::reply::json(&usage))
+}
+
 async fn metrics_handler() -> Result<impl Reply, Rejection> {
     use prometheus::Encoder;
     let encoder = prometheus::TextEncoder::new();
-    let opts = EXPORTEROPTS.try_lock().unwrap().clone();
+    let opts = EXPORTEROPTS.lock().unwrap().clone();
+
+    // NFSv4 clients, the states files are only read when asked.
+    let clients = clients_information(opts.nfsv4_ops_clients);
 
     // Number of clients connected.
-    let number_of_clients = number_of_clients();
+    let number_of_clients = number_of_clients(&clients);
     NUMBER_OF_NFSV4_CLIENTS.set(number_of_clients);
 
+    // Exports and the mounts holding them, read once for every export metric.
+    let exports = etab_exports();
+    let mounts = mountinfo();
+
     // Number of exports.
-    let number_of_exports = number_of_exports();
+    let number_of_exports = number_of_exports(&exports);
     NUMBER_OF_NFSV4_EXPORTS.set(number_of_exports);
 
+    // Export inventory, rebuilt on every scrape as exports come and go.
+    EXPORT_INFO.reset();
+    for export in exports.iter() {
+        let options = &export.options;
+        let access = if options.read_write { "rw" } else { "ro" };
+        let fsid = options.fsid.clone().unwrap_or_default();
+        EXPORT_INFO.with_label_values(&[
+            &export.path,
+            &export.client,
+            access,
+            &options.sync.to_string(),
+            &options.root_squash.to_string(),
+            &options.all_squash.to_string(),
+            &options.secure.to_string(),
+            &options.subtree_check.to_string(),
+            &options.sec.join(":"),
+            &fsid,
+        ]).set(1);
+    }
+
+    // Export security audit.
+    EXPORT_RISK.reset();
+    for (path, rule, at_risk) in export_risks(&exports, &opts.kerberos_only_exports).iter() {
+        EXPORT_RISK.with_label_values(&[path, rule]).set(*at_risk as i64);
+    }
+
+    // Drift between /etc/exports and etab, exportfs -ra not run.
+    let drift = exports_drift(&configured_exports(), &exports);
+    EXPORTS_DRIFT.reset();
+    for (path, client, state) in drift.iter() {
+        EXPORTS_DRIFT.with_label_values(&[path, client, state]).set(1);
+    }
+    EXPORTS_IN_SYNC.set(drift.is_empty() as i64);
+
+    // Health of the storage behind the exported paths.
+    let probes = probe_exports(&exports, opts.export_probe_timeout);
+    EXPORT_PROBE_SUCCESS.reset();
+    EXPORT_PROBE_DURATION.reset();
+    for probe in probes.iter() {
+        EXPORT_PROBE_SUCCESS.with_label_values(&[&probe.path]).set(probe.success as i64);
+        EXPORT_PROBE_DURATION.with_label_values(&[&probe.path]).set(probe.duration.as_secs_f64());
+    }
+
+    // Filesystem capacity of the exported paths, statvfs() would hang as
+    // well on the paths that failed the probe.
+    for family in [&*EXPORT_SIZE_BYTES, &*EXPORT_FREE_BYTES, &*EXPORT_AVAIL_BYTES, &*EXPORT_FILES, &*EXPORT_FILES_FREE] {
+        family.reset();
+    }
+    let healthy_exports: Vec<_> = exports.iter()
+        .filter(|x| probes.iter().any(|y| y.path == x.path && y.success))
+        .cloned()
+        .collect();
+    for capacity in exports_capacity(&healthy_exports, &mounts).iter() {
+        let labels = [capacity.path.as_str(), capacity.fstype.as_str()];
+        EXPORT_SIZE_BYTES.with_label_values(&labels).set(capacity.size);
+        EXPORT_FREE_BYTES.with_label_values(&labels).set(capacity.free);
+        EXPORT_AVAIL_BYTES.with_label_values(&labels).set(capacity.available);
+        EXPORT_FILES.with_label_values(&labels).set(capacity.files);
+        EXPORT_FILES_FREE.with_label_values(&labels).set(capacity.files_free);
+    }
+
+    // Block device I/O statistics of the exported paths, rebuilt as paths
+    // get unexported or moved to another device.
+    for family in [
+        &*EXPORT_DEVICE_READS_COMPLETED, &*EXPORT_DEVICE_READS_MERGED, &*EXPORT_DEVICE_SECTORS_READ,
+        &*EXPORT_DEVICE_READ_TIME, &*EXPORT_DEVICE_WRITES_COMPLETED, &*EXPORT_DEVICE_WRITES_MERGED,
+        &*EXPORT_DEVICE_SECTORS_WRITTEN, &*EXPORT_DEVICE_WRITE_TIME, &*EXPORT_DEVICE_IO_TIME,
+        &*EXPORT_DEVICE_WEIGHTED_IO_TIME,
+    ] {
+        family.reset();
+    }
+    EXPORT_DEVICE_IOS_IN_PROGRESS.reset();
+    for (path, disk) in exports_diskstats(&exports, &mounts).iter() {
+        let labels = [path.as_str(), disk.device.as_str()];
+        EXPORT_DEVICE_IOS_IN_PROGRESS.with_label_values(&labels).set(disk.ios_in_progress);
+        set_counter(&EXPORT_DEVICE_READS_COMPLETED.with_label_values(&labels), disk.reads_completed);
+        set_counter(&EXPORT_DEVICE_READS_MERGED.with_label_values(&labels), disk.reads_merged);
+        set_counter(&EXPORT_DEVICE_SECTORS_READ.with_label_values(&labels), disk.sectors_read);
+        set_counter(&EXPORT_DEVICE_READ_TIME.with_label_values(&labels), disk.read_time_ms);
+        set_counter(&EXPORT_DEVICE_WRITES_COMPLETED.with_label_values(&labels), disk.writes_completed);
+        set_counter(&EXPORT_DEVICE_WRITES_MERGED.with_label_values(&labels), disk.writes_merged);
+        set_counter(&EXPORT_DEVICE_SECTORS_WRITTEN.with_label_values(&labels), disk.sectors_written);
+        set_counter(&EXPORT_DEVICE_WRITE_TIME.with_label_values(&labels), disk.write_time_ms);
+        set_counter(&EXPORT_DEVICE_IO_TIME.with_label_values(&labels), disk.io_time_ms);
+        set_counter(&EXPORT_DEVICE_WEIGHTED_IO_TIME.with_label_values(&labels), disk.weighted_io_time_ms);
+    }
+
+    // Per-export statistics, the counters follow the kernel ones and the
+    // families are rebuilt so unexported paths stop being reported.
+    for family in [&*EXPORT_READ_BYTES, &*EXPORT_WRITE_BYTES, &*EXPORT_STALE_FILEHANDLES] {
+        family.reset();
+    }
+    for export in exports_stats().iter() {
+        let labels = [export.path.as_str(), export.client.as_str()];
+        set_counter(&EXPORT_READ_BYTES.with_label_values(&labels), export.io_read);
+        set_counter(&EXPORT_WRITE_BYTES.with_label_values(&labels), export.io_write);
+        set_counter(&EXPORT_STALE_FILEHANDLES.with_label_values(&labels), export.fh_stale);
+    }
+
+    // NFSv4 clients, rebuilt on every scrape as clients come and go.
+    // Several clients can share a label depending on the --client-label
+    // scheme, their values are merged.
+    let labels: Vec<String> = clients.iter().map(|x| client_label(x, &opts.client_label)).collect();
+    NFSV4_CLIENT_INFO.reset();
+    for (client, label) in clients.iter().zip(labels.iter()) {
+        NFSV4_CLIENT_INFO.with_label_values(&[
+            label,
+            &client.clientid,
+            &client.name,
+            &client.minor_version,
+            &client.implementation_domain,
+            &client.implementation_name,
+            &client.implementation_time,
+            &client.callback_state,
+            &client.callback_address,
+            &client.status,
+        ]).set(1);
+    }
+
+    // Callback channel health, a client with a broken backchannel does not
+    // get delegations and stalls recalls.
+    NFSV4_CLIENT_CALLBACK_STATE.reset();
+    for state in CALLBACK_STATES.iter() {
+        let count = clients.iter().filter(|x| x.callback_state == *state).count();
+        NFSV4_CLIENTS_BY_CALLBACK_STATE.with_label_values(&[state]).set(count as i64);
+    }
+    for (client, label) in clients.iter().zip(labels.iter()).filter(|(x, _)| !x.callback_state.is_empty()) {
+        for state in CALLBACK_STATES.iter() {
+            let gauge = NFSV4_CLIENT_CALLBACK_STATE.with_label_values(&[label, state]);
+            gauge.set(gauge.get().max((client.callback_state == *state) as i64));
+        }
+    }
+
+    // Client leases, courtesy and expirable clients are not counted in
+    // number_of_nfsv4_clients.
+    NFSV4_CLIENT_IDLE_SECONDS.reset();
+    for status in CLIENT_STATUSES.iter() {
+        let count = clients.iter().filter(|x| x.status == *status).count();
+        NFSV4_CLIENTS_BY_STATUS.with_label_values(&[status]).set(count as i64);
+    }
+    let mut idle_seconds: HashMap<&str, i64> = HashMap::new();
+    for (client, label) in clients.iter().zip(labels.iter()) {
+        if let Some(seconds) = client.seconds_from_last_renew {
+            let idle = idle_seconds.entry(label).or_insert(seconds);
+            *idle = (*idle).min(seconds);
+        }
+    }
+    for (label, seconds) in idle_seconds.iter() {
+        NFSV4_CLIENT_IDLE_SECONDS.with_label_values(&[label]).set(*seconds);
+    }
+
     // Number of NFSv4 ops per client.
     // It is disabled by default as it can be CPU intensive
+    // The families are rebuilt so clients gone from /proc/fs/nfsd/clients
+    // stop being reported.
+    for family in [
+        &*OPEN_PER_NFSV4_CLIENT, &*LOCK_PER_NFSV4_CLIENT, &*DELEG_PER_NFSV4_CLIENT, &*LAYOUT_PER_NFSV4_CLIENT,
+        &*WRITE_OPEN_PER_NFSV4_CLIENT, &*DELEG_PER_NFSV4_CLIENT_BY_TYPE,
+        &*NFSV4_STATES, &*NFSV4_CLIENT_EXPORT_ACTIVE, &*NFSV4_HOT_FILE_OPENS, &*NFSV4_HOT_FILE_LOCKS,
+    ] {
+        family.reset();
+    }
     if opts.nfsv4_ops_clients {
-    let ops_per_client = clients_information();
-        for client in ops_per_client.iter() {
-            OPEN_PER_NFSV4_CLIENT.with_label_values(&[&client.address])
-                .set(client.ops_count.t_open);
-            LOCK_PER_NFSV4_CLIENT.with_label_values(&[&client.address])
-                .set(client.ops_count.t_lock);
-            DELEG_PER_NFSV4_CLIENT.with_label_values(&[&client.address])
-                .set(client.ops_count.t_deleg);
-            LAYOUT_PER_NFSV4_CLIENT.with_label_values(&[&client.address])
-                .set(client.ops_count.t_layout);
+        let mut ops_per_client: HashMap<&str, [i64; 7]> = HashMap::new();
+        for (client, label) in clients.iter().zip(labels.iter()) {
+            let ops = ops_per_client.entry(label).or_insert([0; 7]);
+            ops[0] += client.ops_count.t_open;
+            ops[1] += client.ops_count.t_lock;
+            ops[2] += client.ops_count.t_deleg;
+            ops[3] += client.ops_count.t_layout;
+            ops[4] += client.ops_count.t_write_open;
+            ops[5] += client.ops_count.t_read_deleg;
+            ops[6] += client.ops_count.t_write_deleg;
+        }
+        for (label, ops) in ops_per_client.iter() {
+            OPEN_PER_NFSV4_CLIENT.with_label_values(&[label]).set(ops[0]);
+            LOCK_PER_NFSV4_CLIENT.with_label_values(&[label]).set(ops[1]);
+            DELEG_PER_NFSV4_CLIENT.with_label_values(&[label]).set(ops[2]);
+            LAYOUT_PER_NFSV4_CLIENT.with_label_values(&[label]).set(ops[3]);
+            WRITE_OPEN_PER_NFSV4_CLIENT.with_label_values(&[label]).set(ops[4]);
+            DELEG_PER_NFSV4_CLIENT_BY_TYPE.with_label_values(&[label, "read"]).set(ops[5]);
+            DELEG_PER_NFSV4_CLIENT_BY_TYPE.with_label_values(&[label, "write"]).set(ops[6]);
+        }
+
+        // States per export, from the superblock of every state.
+        let devices = exports_devices(&exports, &mounts);
+        for (export, state_type, count) in states_per_export(&clients, &devices).iter() {
+            NFSV4_STATES.with_label_values(&[export, state_type]).set(*count);
+        }
+
+        // Exports each client holds state on.
+        for (client, label) in clients.iter().zip(labels.iter()) {
+            for (device, _) in client_exports(client, &devices).iter() {
+                NFSV4_CLIENT_EXPORT_ACTIVE.with_label_values(&[label, device_export(device)]).set(1);
+            }
+        }
+
+        // Hot files, bounded to --hot-files series each.
+        let files = hot_files(&clients, &devices);
+        for file in most_opened_files(&files, opts.hot_files).iter() {
+            NFSV4_HOT_FILE_OPENS.with_label_values(&[&file.export, &file.filename, &file.inode.to_string()])
+                .set(file.opens);
+        }
+        for file in most_locked_files(&files, opts.hot_files).iter() {
+            NFSV4_HOT_FILE_LOCKS.with_label_values(&[&file.export, &file.filename, &file.inode.to_string()])
+                .set(file.locks);
         }
     }
 
@@ -186,6 +946,14 @@ async fn metrics_handler() -> Result<impl Reply, Rejection> {
     REPLY_CACHE_HITS.set(nfs_stats.reply_cache.hits);
     REPLY_CACHE_MISSES.set(nfs_stats.reply_cache.misses);
     REPLY_CACHE_NOCACHE.set(nfs_stats.reply_cache.nocache);
+    if let Some(reply_cache_stats) = nfs_stats.reply_cache_stats {
+        REPLY_CACHE_MAX_ENTRIES.set(reply_cache_stats.max_entries);
+        REPLY_CACHE_ENTRIES.set(reply_cache_stats.num_entries);
+        REPLY_CACHE_HASH_BUCKETS.set(reply_cache_stats.hash_buckets);
+        REPLY_CACHE_MEMORY.set(reply_cache_stats.mem_usage);
+        set_counter(&REPLY_CACHE_PAYLOAD_MISSES, reply_cache_stats.payload_misses);
+        REPLY_CACHE_LONGEST_CHAIN.set(reply_cache_stats.longest_chain_len);
+    }
 
     // IOBytes
     IOBYTES_READ.set(nfs_stats.io_bytes.read);
@@ -197,6 +965,92 @@ async fn metrics_handler() -> Result<impl Reply, Rejection> {
     NETWORK_TCPCOUNT.set(nfs_stats.network_usage.TCPcount);
     NETWORK_CONNECTIONS.set(nfs_stats.network_usage.TCPconnect);
 
+    // RPC calls
+    set_counter(&RPC_CALLS, nfs_stats.rpc_calls.calls);
+    set_counter(&RPC_BADCALLS, nfs_stats.rpc_calls.badcalls);
+    set_counter(&RPC_BADFMT, nfs_stats.rpc_calls.badfmt);
+    set_counter(&RPC_BADAUTH, nfs_stats.rpc_calls.badauth);
+    set_counter(&RPC_BADCLNT, nfs_stats.rpc_calls.badclnt);
+
+    // Threads
+    THREADS.set(nfs_stats.threads.threads);
+    set_counter(&THREADS_ALL_BUSY, nfs_stats.threads.fullcnt);
+    // Pools are rebuilt so the ones gone after a pool_mode or CPU change
+    // stop being reported, counters restart from the kernel values.
+    for family in [&*POOL_PACKETS_ARRIVED, &*POOL_SOCKETS_ENQUEUED, &*POOL_THREADS_WOKEN, &*POOL_THREADS_TIMEDOUT] {
+        family.reset();
+    }
+    POOL_THREADS.reset();
+    for pool in pool_stats().iter() {
+        POOL_THREADS.with_label_values(&[&pool.pool]).set(pool.threads);
+        set_counter(&POOL_PACKETS_ARRIVED.with_label_values(&[&pool.pool]), pool.packets_arrived);
+        set_counter(&POOL_SOCKETS_ENQUEUED.with_label_values(&[&pool.pool]), pool.sockets_enqueued);
+        set_counter(&POOL_THREADS_WOKEN.with_label_values(&[&pool.pool]), pool.threads_woken);
+        set_counter(&POOL_THREADS_TIMEDOUT.with_label_values(&[&pool.pool]), pool.threads_timedout);
+    }
+
+    // File cache
+    if let Some(file_cache) = file_cache() {
+        FILECACHE_FORMAT_VERSION.set(file_cache.format_version);
+        FILECACHE_ENTRIES.set(file_cache.total_entries);
+        FILECACHE_HASH_BUCKETS.set(file_cache.hash_buckets);
+        FILECACHE_LRU_ENTRIES.set(file_cache.lru_entries);
+        FILECACHE_LONGEST_CHAIN.set(file_cache.longest_chain);
+        set_counter(&FILECACHE_HITS, file_cache.cache_hits);
+        set_counter(&FILECACHE_ACQUISITIONS, file_cache.acquisitions);
+        set_counter(&FILECACHE_ALLOCATIONS, file_cache.allocations);
+        set_counter(&FILECACHE_RELEASES, file_cache.releases);
+        set_counter(&FILECACHE_EVICTIONS, file_cache.evictions);
+        FILECACHE_MEAN_AGE.set(file_cache.mean_age_ms);
+    }
+
+    // In-flight RPC requests, rebuilt on every scrape as requests come and go.
+    // NFSv4 requests are counted under their first operation that is not
+    // SEQUENCE, PUTFH, GETATTR and the like.
+    RPC_INFLIGHT.reset();
+    RPC_INFLIGHT_PER_CLIENT.reset();
+    let mut oldest: f64 = 0.0;
+    let now = monotonic_us();
+    for request in rpc_status().iter() {
+        let operation = inflight_operation(request);
+        RPC_INFLIGHT.with_label_values(&[&operation]).inc();
+        // Same label as the NFSv4 client sending it, when there is one.
+        let address = address_label(&request.client_address, &request.client_port, &[ClientLabel::Address]);
+        let label = match clients.iter().position(|x| x.address == address) {
+            Some(index) => labels[index].clone(),
+            None => address_label(&request.client_address, &request.client_port, &opts.client_label),
+        };
+        RPC_INFLIGHT_PER_CLIENT.with_label_values(&[&label]).inc();
+        oldest = oldest.max((now - request.timestamp_us) as f64 / 1_000_000.0);
+    }
+    RPC_OLDEST_INFLIGHT.set(oldest);
+
+    // Server configuration, label values change when the configuration does.
+    let config = server_config();
+    SERVER_INFO.reset();
+    SERVER_INFO.with_label_values(&[&config.versions.join(","), &config.portlist.join(",")]).set(1);
+    CONFIG_THREADS.set(config.threads);
+    CONFIG_LEASE_TIME.set(config.lease_time);
+    CONFIG_GRACE_TIME.set(config.grace_time);
+    CONFIG_MAX_BLOCK_SIZE.set(config.max_block_size);
+    CONFIG_MAX_CONNECTIONS.set(config.max_connections);
+    MODULE_PARAMETER_INFO.reset();
+    for (parameter, value) in config.module_parameters.iter() {
+        MODULE_PARAMETER_INFO.with_label_values(&[parameter, value]).set(1);
+    }
+
+    // NFSv4 operations
+    for (op, count) in nfs_stats.nfsv4_ops.operations.iter() {
+        set_counter(&NFSV4_OPERATIONS.with_label_values(&[op]), *count);
+    }
+
+    // NFSv2 and NFSv3 procedures
+    for procs in [&nfs_stats.nfsv2_procs, &nfs_stats.nfsv3_procs] {
+        for (procedure, count) in procs.procedures.iter() {
+            set_counter(&NFS_PROCEDURES.with_label_values(&[&procs.version, procedure]), *count);
+        }
+    }
+
     let mut buffer = Vec::new();
     if let Err(e) = encoder.encode(&REGISTRY.gather(), &mut buffer) {
         eprintln!("could not encode custom metrics: {}", e);
@@ -235,6 +1089,22 @@ pub async fn start_prometheus(options: &ArgMatches) -> Result<(), ()> {
 
     let expopts: ExporterOptions = ExporterOptions {
         nfsv4_ops_clients: options.is_present("nfsv4opsclients"),
+        kerberos_only_exports: match options.value_of("kerberos-only-exports") {
+            Some(paths) => paths.split(',').map(|x| x.trim().to_string()).collect(),
+            None => Vec::new(),
+        },
+        export_probe_timeout: match options.value_of("export-probe-timeout") {
+            Some(timeout) => Duration::from_secs_f64(timeout.parse::<f64>().expect("Could not parse export probe timeout")),
+            None => Duration::from_secs(5),
+        },
+        client_label: match options.value_of("client-label") {
+            Some(scheme) => parse_client_label(scheme).expect("Could not parse client label"),
+            None => vec![ClientLabel::Address],
+        },
+        hot_files: match options.value_of("hot-files") {
+            Some(n) => n.parse::<usize>().expect("Could not parse number of hot files"),
+            None => 10,
+        },
      };
 
     // XXX: It is safe to use unwrap() here
@@ -254,10 +1124,12 @@ pub async fn start_prometheus(options: &ArgMatches) -> Result<(), ()> {
     register_metrics();
 
     let metrics_route = warp::path!("metrics").and_then(metrics_handler);
+    let hot_files_route = warp::path!("hotfiles").and_then(hot_files_handler);
+    let client_exports_route = warp::path!("clientexports").and_then(client_exports_handler);
     let route = warp::path::end().and_then(index_handler);
 
     println!("Exporter started on IP: {}, Port: {}", default_address, default_port);
-    warp::serve(metrics_route.or(route))
+    warp::serve(metrics_route.or(hot_files_route).or(client_exports_route).or(route))
         .run(addr_convert)
         .await;
 
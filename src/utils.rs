@@ -1 +1,3 @@
 pub mod helper;
+pub mod mountinfo;
+pub mod diskstats;
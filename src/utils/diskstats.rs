@@ -0,0 +1,95 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause
+ *
+ * BSD 2-Clause License
+ *
+ * Copyright (c) 2021-2023, Gandi S.A.S.
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice, this
+ *    list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use crate::utils::helper::{path_exists, wrapper_read};
+
+pub const PROC_DISKSTATS: &str = "/proc/diskstats";
+
+#[derive(Debug, Clone, Default)]
+pub struct DiskStats {
+    pub major: u64,
+    pub minor: u64,
+    // kernel name of the device, "sda", "dm-0", "nvme0n1p1"...
+    pub device: String,
+    pub reads_completed: i64,
+    pub reads_merged: i64,
+    pub sectors_read: i64,
+    // milliseconds spent reading.
+    pub read_time_ms: i64,
+    pub writes_completed: i64,
+    pub writes_merged: i64,
+    pub sectors_written: i64,
+    // milliseconds spent writing.
+    pub write_time_ms: i64,
+    // number of I/Os currently in progress.
+    pub ios_in_progress: i64,
+    // milliseconds spent doing I/Os.
+    pub io_time_ms: i64,
+    // io_time_ms weighted by the number of I/Os in progress, the time
+    // requests spent in the queue.
+    pub weighted_io_time_ms: i64,
+}
+
+// Each line looks like "<major> <minor> <device> <11 counters> [discard and
+// flush counters]", only the first 11 counters exist on every kernel.
+pub fn diskstats() -> Vec<DiskStats> {
+    let mut disks: Vec<DiskStats> = Vec::new();
+
+    if path_exists(PROC_DISKSTATS) {
+        let content = wrapper_read(PROC_DISKSTATS);
+
+        for line in content.iter() {
+            let data: Vec<&str> = line.split_whitespace().collect();
+            if data.len() < 14 {
+                continue;
+            }
+            let value = |index: usize| data[index].parse::<i64>().unwrap_or(0);
+
+            disks.push(DiskStats {
+                major: data[0].parse::<u64>().unwrap_or(0),
+                minor: data[1].parse::<u64>().unwrap_or(0),
+                device: data[2].to_string(),
+                reads_completed: value(3),
+                reads_merged: value(4),
+                sectors_read: value(5),
+                read_time_ms: value(6),
+                writes_completed: value(7),
+                writes_merged: value(8),
+                sectors_written: value(9),
+                write_time_ms: value(10),
+                ios_in_progress: value(11),
+                io_time_ms: value(12),
+                weighted_io_time_ms: value(13),
+            });
+        }
+    }
+
+    disks
+}
@@ -30,6 +30,7 @@
 extern crate nix;
 extern crate semver;
 use nix::sys::utsname::*;
+use nix::time::{clock_gettime, ClockId};
 use semver::Version;
 
 use std::path::Path;
@@ -66,6 +67,9 @@ pub fn wrapper_read<P>(filename: P) -> Vec<String> where P: AsRef<Path>,
 pub const PROC_NFSDV4: &'static str = "/proc/fs/nfsd/";
 pub const VAR_NFSDV4: &'static str = "/var/lib/nfs/";
 pub const PROC_RPC: &'static str = "/proc/net/rpc/";
+pub const ETC_EXPORTS: &'static str = "/etc/exports";
+pub const ETC_EXPORTS_D: &'static str = "/etc/exports.d/";
+pub const SYS_MODULE_NFSD: &'static str = "/sys/module/nfsd/parameters/";
 
 // Linux kernel
 const LINUX_MINIMAL_VERSION: &'static str = "5.3.0";
@@ -87,6 +91,50 @@ pub fn is_kernel_compatible() -> bool {
     false
 }
 
+// Paths in /proc and /var/lib/nfs have spaces, tabs, newlines and
+// backslashes escaped as octal (\040 for a space).
+pub fn unescape_octal(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut unescaped: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'\\' && i + 3 < bytes.len() && bytes[i + 1..i + 4].iter().all(|x| (b'0'..=b'7').contains(x)) {
+            unescaped.push(u8::from_str_radix(&value[i + 1..i + 4], 8).unwrap_or(b'?'));
+            i += 4;
+        } else {
+            unescaped.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8_lossy(&unescaped).to_string()
+}
+
+// Microseconds since boot, the clock nfsd uses to timestamp requests.
+#[inline]
+pub fn monotonic_us() -> i64 {
+    match clock_gettime(ClockId::CLOCK_MONOTONIC) {
+        Ok(now) => now.tv_sec() * 1_000_000 + now.tv_nsec() / 1_000,
+        Err(_) => 0,
+    }
+}
+
+// Split "192.168.0.1:740" or "[fe80::1]:740" into address and port, the
+// brackets of an IPv6 address are dropped. A bare IPv6 address has no port.
+pub fn split_address_port(address: &str) -> (String, String) {
+    if let Some(address) = address.strip_prefix('[') {
+        return match address.split_once("]:") {
+            Some((ip, port)) => (ip.to_string(), port.to_string()),
+            None => (address.trim_end_matches(']').to_string(), String::new()),
+        };
+    }
+    match address.rsplit_once(':') {
+        Some((ip, port)) if !ip.contains(':') => (ip.to_string(), port.to_string()),
+        _ => (address.to_string(), String::new()),
+    }
+}
+
 #[inline]
 pub fn path_exists(path: &str) -> bool {
     Path::new(path).exists()
@@ -0,0 +1,91 @@
+/*-
+ * SPDX-License-Identifier: BSD-2-Clause
+ *
+ * BSD 2-Clause License
+ *
+ * Copyright (c) 2021-2023, Gandi S.A.S.
+ * All rights reserved.
+ *
+ * Redistribution and use in source and binary forms, with or without
+ * modification, are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice, this
+ *    list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+ * AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+ * IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+ * DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+ * FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+ * DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+ * SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+ * CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+ * OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+ * OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+use crate::utils::helper::{path_exists, unescape_octal, wrapper_read};
+use std::path::Path;
+
+pub const PROC_MOUNTINFO: &str = "/proc/self/mountinfo";
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct MountInfo {
+    // device numbers of the mounted filesystem.
+    pub major: u64,
+    pub minor: u64,
+    // directory of the filesystem mounted here, "/" unless bind mounted.
+    pub root: String,
+    pub mount_point: String,
+    pub fstype: String,
+    // device or server the filesystem comes from, "/dev/sda1"...
+    pub source: String,
+}
+
+// Each line looks like:
+// "36 35 98:0 /mnt1 /mnt/parent rw,noatime master:1 - ext3 /dev/root rw"
+// the number of optional fields before "-" varies.
+pub fn mountinfo() -> Vec<MountInfo> {
+    let mut mounts: Vec<MountInfo> = Vec::new();
+
+    if path_exists(PROC_MOUNTINFO) {
+        let content = wrapper_read(PROC_MOUNTINFO);
+
+        for line in content.iter() {
+            let data: Vec<&str> = line.split(' ').collect();
+            let separator = match data.iter().position(|x| *x == "-") {
+                Some(index) if index >= 5 && data.len() > index + 2 => index,
+                _ => continue,
+            };
+            let (major, minor) = data[2].split_once(':').unwrap_or(("0", "0"));
+
+            mounts.push(MountInfo {
+                major: major.parse::<u64>().unwrap_or(0),
+                minor: minor.parse::<u64>().unwrap_or(0),
+                root: unescape_octal(data[3]),
+                mount_point: unescape_octal(data[4]),
+                fstype: data[separator + 1].to_string(),
+                source: unescape_octal(data[separator + 2]),
+            });
+        }
+    }
+
+    mounts
+}
+
+// Mount holding `path`: the deepest mount point above it, the last one
+// mounted when several are stacked on the same directory.
+pub fn mount_for_path<'a>(mounts: &'a [MountInfo], path: &str) -> Option<&'a MountInfo> {
+    let path = Path::new(path);
+    mounts.iter()
+        .filter(|x| path.starts_with(&x.mount_point))
+        .fold(None, |found: Option<&MountInfo>, x| match found {
+            Some(y) if y.mount_point.len() > x.mount_point.len() => Some(y),
+            _ => Some(x),
+        })
+}